@@ -0,0 +1,102 @@
+use std::{path::Path, sync::Mutex};
+
+use tauri::Manager;
+
+use crate::{io_err, saved_radials_directory};
+
+/// Whether the radials directory is readable, once it's been checked. On
+/// macOS the first read of a protected folder (Documents, Desktop, an
+/// external drive) raises the system permission prompt, so the answer is
+/// kept in managed state and the fs commands reuse it instead of probing
+/// (and prompting) each time.
+#[derive(Default)]
+pub struct FileAccessState {
+    granted: Mutex<Option<bool>>,
+}
+
+impl FileAccessState {
+    fn get(&self) -> Option<bool> {
+        self.granted.lock().ok().and_then(|g| *g)
+    }
+
+    fn set(&self, value: Option<bool>) {
+        if let Ok(mut granted) = self.granted.lock() {
+            *granted = value;
+        }
+    }
+}
+
+/// None when there's nothing to probe: no directory chosen yet, or one that
+/// doesn't exist (the listing commands report that themselves).
+#[cfg(target_os = "macos")]
+fn probe(dir: Option<&Path>) -> Option<bool> {
+    let dir = dir.filter(|dir| dir.exists())?;
+    match std::fs::read_dir(dir) {
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => Some(false),
+        _ => Some(true),
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn probe(_dir: Option<&Path>) -> Option<bool> {
+    Some(true)
+}
+
+// Probes and caches the answer, granted or denied. With nothing to probe
+// the state stays unset and access is assumed.
+fn probe_and_cache(app: &tauri::AppHandle, state: &FileAccessState) -> Result<bool, String> {
+    let dir = saved_radials_directory(app)?;
+    let granted = probe(dir.as_deref().map(Path::new));
+    state.set(granted);
+    Ok(granted.unwrap_or(true))
+}
+
+/// The check the fs commands run before touching the disk. A cached answer
+/// is used as is, so a denial fails fast without another prompt; only an
+/// unknown state is probed.
+pub fn ensure_file_access(app: &tauri::AppHandle) -> Result<(), String> {
+    let state = app.state::<FileAccessState>();
+    let granted = match state.get() {
+        Some(granted) => granted,
+        None => probe_and_cache(app, &state)?,
+    };
+    if granted {
+        Ok(())
+    } else {
+        Err(io_err(
+            "access to the radials directory was denied; allow it in System Settings > \
+             Privacy & Security > Files and Folders, then retry",
+        ))
+    }
+}
+
+/// Runs an fs command behind ensure_file_access. A grant can be taken back
+/// in System Settings while the app runs, so when the command fails the
+/// cached grant is dropped and the next fs command probes again.
+pub fn with_file_access<T>(
+    app: &tauri::AppHandle,
+    run: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    ensure_file_access(app)?;
+    let result = run();
+    if result.is_err() {
+        let state = app.state::<FileAccessState>();
+        if state.get() == Some(true) {
+            state.set(None);
+        }
+    }
+    result
+}
+
+/// Checks that the saved radials directory can be read, raising the macOS
+/// prompt if it hasn't been answered yet, and returns the answer. A grant is
+/// reused as cached; a denial is probed again, since this is what the
+/// frontend calls when the user retries after changing System Settings.
+#[tauri::command]
+pub fn request_file_access(app: tauri::AppHandle) -> Result<bool, String> {
+    let state = app.state::<FileAccessState>();
+    if state.get() == Some(true) {
+        return Ok(true);
+    }
+    probe_and_cache(&app, &state)
+}
@@ -0,0 +1,368 @@
+use std::{
+    fs,
+    io::{Cursor, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use crate::{
+    app_data_dir, backups, fmt_path, io_err, library, menu, palette, saved_radials_directory,
+    write_file_atomic, RADIALS_DIR_MARKER,
+};
+
+/// Entries bigger than this are refused on import; no menu or state file
+/// comes anywhere near it, so it only trips on corrupt or hostile archives.
+const MAX_ENTRY_BYTES: u64 = 64 * 1024 * 1024;
+
+// ---------- Zip helpers ----------
+pub fn zip_entries(entries: &[(String, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    for (name, bytes) in entries {
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| io_err(format!("zip {name} failed: {e}")))?;
+        zip.write_all(bytes)
+            .map_err(|e| io_err(format!("zip {name} failed: {e}")))?;
+    }
+    let out = zip
+        .finish()
+        .map_err(|e| io_err(format!("finish zip failed: {e}")))?;
+    Ok(out.into_inner())
+}
+
+/// Every file in the archive as `(name, bytes)`, directories skipped. Names
+/// are returned as stored; callers must still resolve them safely.
+pub fn unzip_entries(path: &Path) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let file =
+        fs::File::open(path).map_err(|e| io_err(format!("open {} failed: {e}", fmt_path(path))))?;
+    let mut zip = ZipArchive::new(file)
+        .map_err(|e| io_err(format!("{} is not a zip archive: {e}", fmt_path(path))))?;
+    let mut entries = vec![];
+    for i in 0..zip.len() {
+        let mut entry = zip
+            .by_index(i)
+            .map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(path))))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        if entry.size() > MAX_ENTRY_BYTES {
+            return Err(io_err(format!("{name} is too large to import")));
+        }
+        let mut bytes = vec![];
+        entry
+            .by_ref()
+            .take(MAX_ENTRY_BYTES)
+            .read_to_end(&mut bytes)
+            .map_err(|e| io_err(format!("read {name} failed: {e}")))?;
+        entries.push((name, bytes));
+    }
+    Ok(entries)
+}
+
+// ---------- Full backup ----------
+// Layout: manifest.json, app/<state file>, radials/<relative menu path>.
+const MANIFEST: &str = "manifest.json";
+const APP_PREFIX: &str = "app/";
+const RADIALS_PREFIX: &str = "radials/";
+
+// The radials directory marker is a path on this machine, so it isn't
+// carried over; older archives that include it have it skipped on restore.
+fn is_state_file(name: &str) -> bool {
+    !name.ends_with(".tmp") && !name.ends_with(".bak") && name != RADIALS_DIR_MARKER
+}
+
+// Every top-level file in app_data_dir: preferences, commands, ... Logs
+// live in a subfolder and are left out.
+fn app_state_entries(app: &tauri::AppHandle) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let data_dir = app_data_dir(app)?;
+    let mut entries = vec![];
+    let listing = fs::read_dir(&data_dir)
+        .map_err(|e| io_err(format!("read_dir {} failed: {e}", fmt_path(&data_dir))))?;
+    for entry in listing {
+        let entry = entry.map_err(|e| io_err(format!("dir entry error: {e}")))?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !path.is_file() || !is_state_file(&name) {
+            continue;
+        }
+        let bytes =
+            fs::read(&path).map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(&path))))?;
+        entries.push((format!("{APP_PREFIX}{name}"), bytes));
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+/// Zips the app's state files together with every menu in the saved
+/// radials directory.
+#[tauri::command]
+pub fn export_full_backup(out_path: String, app: tauri::AppHandle) -> Result<(), String> {
+    let mut entries = app_state_entries(&app)?;
+
+    let radials = saved_radials_directory(&app)?;
+    if let Some(dir) = &radials {
+        let dir = Path::new(dir);
+        for rel in library::menu_paths(dir, true)? {
+            let path = dir.join(&rel);
+            let bytes = fs::read(&path)
+                .map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(&path))))?;
+            entries.push((format!("{RADIALS_PREFIX}{rel}"), bytes));
+        }
+    }
+
+    let manifest = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "radials_directory": radials,
+    });
+    entries.insert(0, (MANIFEST.to_string(), manifest.to_string().into_bytes()));
+    write_file_atomic(Path::new(&out_path), &zip_entries(&entries)?)
+}
+
+/// Just the app's own state files, in the full backup layout, so the
+/// archive restores with import_full_backup.
+#[tauri::command]
+pub fn export_app_state(out_path: String, app: tauri::AppHandle) -> Result<(), String> {
+    let mut entries = app_state_entries(&app)?;
+    let manifest = json!({ "version": env!("CARGO_PKG_VERSION") });
+    entries.insert(0, (MANIFEST.to_string(), manifest.to_string().into_bytes()));
+    write_file_atomic(Path::new(&out_path), &zip_entries(&entries)?)
+}
+
+fn state_target(data_dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let valid = !name.is_empty()
+        && is_state_file(name)
+        && Path::new(name)
+            .file_name()
+            .map(|f| f == name)
+            .unwrap_or(false);
+    if !valid {
+        return Err(io_err(format!("backup has an invalid app file {name}")));
+    }
+    Ok(data_dir.join(name))
+}
+
+// An existing state file is moved aside to `<name>.<timestamp>.bak` first.
+fn restore_state_file(target: &Path, bytes: &[u8]) -> Result<(), String> {
+    if target.exists() {
+        let mut aside = target.as_os_str().to_owned();
+        aside.push(format!(".{}.bak", backups::timestamp_now()));
+        fs::rename(target, &aside)
+            .map_err(|e| io_err(format!("back up {} failed: {e}", fmt_path(target))))?;
+    }
+    write_file_atomic(target, bytes)
+}
+
+/// Restores an export_full_backup archive. State files go back into
+/// app_data_dir and menus into the radials directory currently in use (an
+/// archived marker names a path that may be on another machine, so it's
+/// never restored); anything they replace is kept (state files as `.bak`,
+/// menus in `.history`). Every entry is checked before anything is written,
+/// so a bad archive changes nothing. Returns the paths written.
+#[tauri::command]
+pub fn import_full_backup(path: String, app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    let entries = unzip_entries(Path::new(&path))?;
+    if !entries.iter().any(|(name, _)| name == MANIFEST) {
+        return Err(io_err(format!(
+            "{path} is not a full backup (no {MANIFEST})"
+        )));
+    }
+    let radials_dir = saved_radials_directory(&app)?.map(PathBuf::from);
+    let written = restore_entries(&entries, &app_data_dir(&app)?, radials_dir)?;
+    Ok(written.iter().map(|p| fmt_path(p)).collect())
+}
+
+fn restore_entries(
+    entries: &[(String, Vec<u8>)],
+    data_dir: &Path,
+    radials_dir: Option<PathBuf>,
+) -> Result<Vec<PathBuf>, String> {
+    let states = entries
+        .iter()
+        .filter_map(|(name, bytes)| Some((name.strip_prefix(APP_PREFIX)?, bytes)))
+        .filter(|(file, _)| *file != RADIALS_DIR_MARKER)
+        .map(|(file, bytes)| Ok((state_target(data_dir, file)?, bytes)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let menus: Vec<_> = entries
+        .iter()
+        .filter_map(|(name, bytes)| Some((name.strip_prefix(RADIALS_PREFIX)?, bytes)))
+        .collect();
+    let mut menu_targets = vec![];
+    if !menus.is_empty() {
+        let dir = radials_dir.filter(|dir| dir.is_dir()).ok_or_else(|| {
+            io_err("backup has menus but no radials directory to restore them to")
+        })?;
+        for (rel, bytes) in menus {
+            let target = library::resolve_relative(&dir, rel)?;
+            serde_json::from_slice::<Value>(bytes)
+                .map_err(|e| io_err(format!("{rel}: parse failed: {e}")))?;
+            menu_targets.push((target, bytes));
+        }
+    }
+
+    let mut written: Vec<PathBuf> = vec![];
+    for (target, bytes) in states {
+        restore_state_file(&target, bytes)?;
+        written.push(target);
+    }
+    for (target, bytes) in menu_targets {
+        backups::backup_existing(&target)?;
+        write_file_atomic(&target, bytes)?;
+        written.push(target);
+    }
+    Ok(written)
+}
+
+// ---------- Menu bundles ----------
+// A `.rmbundle` is a zip: manifest.json, the active commands.json and
+// menus/<relative menu path>.
+const BUNDLE_VERSION: u64 = 1;
+const BUNDLE_COMMANDS: &str = "commands.json";
+const BUNDLE_MENUS_PREFIX: &str = "menus/";
+
+/// Packs every menu in `directory` together with the commands they were
+/// built against (the merged set the app is using), so the recipient gets
+/// one file instead of a loose folder.
+#[tauri::command]
+pub fn export_radials_bundle(
+    directory: String,
+    out_path: String,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    let dir = Path::new(&directory);
+    let menus = library::menu_paths(dir, true)?;
+    let commands = palette::merged_commands(&app)?;
+    let manifest = json!({
+        "bundle_version": BUNDLE_VERSION,
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "commands": BUNDLE_COMMANDS,
+        "menus": menus,
+    });
+    let mut entries = vec![
+        (MANIFEST.to_string(), manifest.to_string().into_bytes()),
+        (
+            BUNDLE_COMMANDS.to_string(),
+            serde_json::to_vec_pretty(&commands)
+                .map_err(|e| io_err(format!("serialize commands failed: {e}")))?,
+        ),
+    ];
+    for rel in &menus {
+        let path = dir.join(rel);
+        let bytes =
+            fs::read(&path).map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(&path))))?;
+        entries.push((format!("{BUNDLE_MENUS_PREFIX}{rel}"), bytes));
+    }
+    write_file_atomic(Path::new(&out_path), &zip_entries(&entries)?)
+}
+
+/// Unpacks a bundle's menus into `target_directory`. Nothing is written
+/// unless every menu is valid and none would replace a different existing
+/// file; ones already present with identical content are skipped. The
+/// bundled commands.json is left in the bundle, since it describes the
+/// sender's setup. Returns the relative paths written.
+#[tauri::command]
+pub fn import_radials_bundle(
+    bundle_path: String,
+    target_directory: String,
+) -> Result<Vec<String>, String> {
+    let entries = unzip_entries(Path::new(&bundle_path))?;
+    let manifest = entries
+        .iter()
+        .find(|(name, _)| name == MANIFEST)
+        .and_then(|(_, bytes)| serde_json::from_slice::<Value>(bytes).ok())
+        .ok_or_else(|| io_err(format!("{bundle_path} has no readable {MANIFEST}")))?;
+    match manifest.get("bundle_version").and_then(Value::as_u64) {
+        Some(v) if (1..=BUNDLE_VERSION).contains(&v) => {}
+        Some(v) => {
+            return Err(io_err(format!(
+                "bundle version {v} is newer than this build supports ({BUNDLE_VERSION})"
+            )))
+        }
+        None => return Err(io_err(format!("{MANIFEST} has no bundle_version"))),
+    }
+
+    let dir = PathBuf::from(&target_directory);
+    let mut pending = vec![];
+    let mut errors = vec![];
+    for (name, bytes) in &entries {
+        let Some(rel) = name.strip_prefix(BUNDLE_MENUS_PREFIX) else {
+            continue;
+        };
+        let target = match library::resolve_relative(&dir, rel) {
+            Ok(target) => target,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        match serde_json::from_slice::<Value>(bytes) {
+            Ok(menu) => {
+                if let Err(problems) = menu::validate_menu(&menu) {
+                    errors.push(format!("{rel}: {}", problems.join("; ")));
+                    continue;
+                }
+            }
+            Err(e) => {
+                errors.push(format!("{rel}: parse failed: {e}"));
+                continue;
+            }
+        }
+        if target.exists() {
+            let existing = fs::read(&target)
+                .map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(&target))))?;
+            if existing == *bytes {
+                continue;
+            }
+            errors.push(format!("{rel} already exists with different content"));
+            continue;
+        }
+        pending.push((rel.to_string(), target, bytes));
+    }
+    if !errors.is_empty() {
+        return Err(io_err(errors.join("\n")));
+    }
+    for (_, target, bytes) in &pending {
+        write_file_atomic(target, bytes)?;
+    }
+    Ok(pending.into_iter().map(|(rel, _, _)| rel).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_keeps_the_current_radials_directory_marker() {
+        let data = tempfile::tempdir().unwrap();
+        let radials = tempfile::tempdir().unwrap();
+        let marker = data.path().join(RADIALS_DIR_MARKER);
+        fs::write(&marker, "/this/machine/Radials").unwrap();
+        let entries = vec![
+            (MANIFEST.to_string(), b"{}".to_vec()),
+            (
+                format!("{APP_PREFIX}{RADIALS_DIR_MARKER}"),
+                b"C:\\Users\\old\\Radials".to_vec(),
+            ),
+            (format!("{APP_PREFIX}preferences.json"), b"{}".to_vec()),
+            (format!("{RADIALS_PREFIX}Modeling.json"), b"{}".to_vec()),
+        ];
+
+        let written =
+            restore_entries(&entries, data.path(), Some(radials.path().to_path_buf())).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&marker).unwrap(),
+            "/this/machine/Radials"
+        );
+        assert_eq!(
+            written,
+            [
+                data.path().join("preferences.json"),
+                radials.path().join("Modeling.json")
+            ]
+        );
+    }
+}
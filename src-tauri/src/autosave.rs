@@ -0,0 +1,67 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::{fmt_path, io_err, menu, read_json_file, write_json_file};
+
+/// Autosaves sit beside the menus like backups do, one file per menu:
+/// `<dir>/.autosave/<menu>.json`. The leading dot keeps them out of listings.
+pub const AUTOSAVE_DIR: &str = ".autosave";
+
+pub fn autosave_path(menu_path: &Path) -> Result<PathBuf, String> {
+    let name = menu_path
+        .file_name()
+        .ok_or_else(|| io_err(format!("{} has no file name", fmt_path(menu_path))))?;
+    let dir = menu_path.parent().unwrap_or_else(|| Path::new("."));
+    Ok(dir.join(AUTOSAVE_DIR).join(name))
+}
+
+/// Drops the autosave once the real file has caught up with it.
+pub fn clear(menu_path: &Path) -> Result<(), String> {
+    let path = autosave_path(menu_path)?;
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(io_err(format!("delete {} failed: {e}", fmt_path(&path)))),
+    }
+}
+
+// ---------- Commands ----------
+/// Stores unsaved edits for `path` without touching the menu itself.
+#[tauri::command]
+pub fn autosave_menu(menu: Value, path: String) -> Result<(), String> {
+    write_json_file(&autosave_path(Path::new(&path))?, &menu)
+}
+
+#[tauri::command]
+pub fn get_autosave(path: String) -> Result<Option<Value>, String> {
+    let autosave = autosave_path(Path::new(&path))?;
+    if !autosave.is_file() {
+        return Ok(None);
+    }
+    read_json_file(&autosave).map(Some)
+}
+
+#[tauri::command]
+pub fn discard_autosave(path: String) -> Result<(), String> {
+    clear(Path::new(&path))
+}
+
+/// What the autosave would change in the saved file, or None without an
+/// autosave. A menu that was never saved diffs against an empty ring.
+#[tauri::command]
+pub fn diff_autosave(path: String) -> Result<Option<menu::MenuDiff>, String> {
+    let Some(autosave) = get_autosave(path.clone())? else {
+        return Ok(None);
+    };
+    let path = Path::new(&path);
+    let saved = if path.is_file() {
+        read_json_file(path)?
+    } else {
+        serde_json::json!({ "items": [] })
+    };
+    menu::diff_menus(&saved, &autosave).map(Some)
+}
@@ -0,0 +1,467 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::{fmt_path, io_err, library, menu, prefs, read_json_file, write_file_atomic};
+
+/// Saved versions live beside the menus, one folder per menu:
+/// `<dir>/.history/<menu name>/<timestamp>.json`.
+pub const HISTORY_DIR: &str = ".history";
+
+/// Where builds before `.history` kept them:
+/// `<dir>/.backups/<menu>.json/<timestamp>.mmmZ.json`. Moved over the first
+/// time a menu's history is read.
+const LEGACY_BACKUP_DIR: &str = ".backups";
+
+// ---------- Timestamps ----------
+/// UTC time as `YYYY-MM-DDTHH-MM-SS`; dashes instead of colons keep the
+/// name valid on Windows, and the fixed width makes names sort by time.
+pub fn timestamp_now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let (y, m, d) = civil_from_days(secs.div_euclid(86_400));
+    let rem = secs.rem_euclid(86_400);
+    format!(
+        "{y:04}-{m:02}-{d:02}T{:02}-{:02}-{:02}",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+// Howard Hinnant's days-to-civil conversion.
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
+
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let m = i64::from(m);
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + i64::from(d) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Milliseconds since the epoch for a timestamp_now() string, or None for a
+/// name that isn't one (a hand-placed file, say). A `-N` suffix, which marks
+/// a later save within the same second, is ignored.
+pub fn parse_timestamp(stamp: &str) -> Option<i64> {
+    let stamp = split_stamp(stamp).0;
+    let b = stamp.as_bytes();
+    if b.len() != 19 || b[10] != b'T' {
+        return None;
+    }
+    let num = |range: std::ops::Range<usize>| stamp.get(range)?.parse::<i64>().ok();
+    let (y, mo, d) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (h, mi, s) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if !(1..=12).contains(&mo) || !(1..=31).contains(&d) || h > 23 || mi > 59 || s > 59 {
+        return None;
+    }
+    let days = days_from_civil(y, mo as u32, d as u32);
+    Some((((days * 24 + h) * 60 + mi) * 60 + s) * 1000)
+}
+
+// `2024-05-01T12-30-00-2` -> (`2024-05-01T12-30-00`, 2); a plain stamp is
+// the first save of its second.
+fn split_stamp(stamp: &str) -> (&str, u32) {
+    let suffix = stamp
+        .get(19..)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .and_then(|n| n.parse().ok());
+    match suffix {
+        Some(n) => (&stamp[..19], n),
+        None => (stamp, 1),
+    }
+}
+
+// ---------- Locations ----------
+pub fn backup_folder(menu_path: &Path) -> Result<PathBuf, String> {
+    let name = menu_path
+        .file_stem()
+        .ok_or_else(|| io_err(format!("{} has no file name", fmt_path(menu_path))))?;
+    let dir = menu_path.parent().unwrap_or_else(|| Path::new("."));
+    Ok(dir.join(HISTORY_DIR).join(name))
+}
+
+// Two changed saves within one second would share a name; the later copy
+// gets a `-2`, `-3`, ... suffix and keeps the real time.
+fn free_backup_path(folder: &Path, stamp: &str) -> PathBuf {
+    let mut dest = folder.join(format!("{stamp}.json"));
+    let mut n = 2;
+    while dest.exists() {
+        dest = folder.join(format!("{stamp}-{n}.json"));
+        n += 1;
+    }
+    dest
+}
+
+fn migrate_legacy_backups(menu_path: &Path) -> Result<(), String> {
+    let (Some(dir), Some(name)) = (menu_path.parent(), menu_path.file_name()) else {
+        return Ok(());
+    };
+    let legacy = dir.join(LEGACY_BACKUP_DIR).join(name);
+    if !legacy.is_dir() {
+        return Ok(());
+    }
+    let mut files = vec![];
+    let entries = fs::read_dir(&legacy)
+        .map_err(|e| io_err(format!("read_dir {} failed: {e}", fmt_path(&legacy))))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| io_err(format!("dir entry error: {e}")))?;
+        let path = entry.path();
+        if path.extension().map(|x| x == "json").unwrap_or(false) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    let folder = backup_folder(menu_path)?;
+    fs::create_dir_all(&folder)
+        .map_err(|e| io_err(format!("create dir {} failed: {e}", fmt_path(&folder))))?;
+    for file in files {
+        let stem = file
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        // `2024-05-01T12-30-00.123Z` keeps its second; anything else keeps
+        // its name.
+        let stamp = stem
+            .get(..19)
+            .filter(|s| parse_timestamp(s).is_some())
+            .unwrap_or(&stem);
+        let dest = free_backup_path(&folder, stamp);
+        fs::rename(&file, &dest)
+            .map_err(|e| io_err(format!("move {} failed: {e}", fmt_path(&file))))?;
+    }
+    // Only succeeds once nothing else is left in them.
+    let _ = fs::remove_dir(&legacy);
+    let _ = fs::remove_dir(dir.join(LEGACY_BACKUP_DIR));
+    Ok(())
+}
+
+fn content_hash(path: &Path) -> Result<[u8; 32], String> {
+    let bytes =
+        fs::read(path).map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(path))))?;
+    Ok(Sha256::digest(bytes).into())
+}
+
+/// Copies the current file into its backup folder before it gets replaced.
+/// Does nothing when there is no file yet, or when the newest backup already
+/// holds the same bytes (a save with no changes).
+pub fn backup_existing(menu_path: &Path) -> Result<(), String> {
+    if !menu_path.is_file() {
+        return Ok(());
+    }
+    if let Some((_, newest)) = list_backups(menu_path)?.last() {
+        if content_hash(newest)? == content_hash(menu_path)? {
+            return Ok(());
+        }
+    }
+    let folder = backup_folder(menu_path)?;
+    fs::create_dir_all(&folder)
+        .map_err(|e| io_err(format!("create dir {} failed: {e}", fmt_path(&folder))))?;
+    let dest = free_backup_path(&folder, &timestamp_now());
+    fs::copy(menu_path, &dest)
+        .map_err(|e| io_err(format!("backup to {} failed: {e}", fmt_path(&dest))))?;
+    prune_backups(menu_path, prefs::active().history_limit)
+}
+
+// Runs only once the new backup is complete, and only ever deletes the
+// oldest files, so the live menu and its newest backups survive a crash at
+// any point. At least one backup is always kept.
+fn prune_backups(menu_path: &Path, keep: usize) -> Result<(), String> {
+    let backups = list_backups(menu_path)?;
+    let excess = backups.len().saturating_sub(keep.max(1));
+    for (_, file) in &backups[..excess] {
+        fs::remove_file(file)
+            .map_err(|e| io_err(format!("delete {} failed: {e}", fmt_path(file))))?;
+    }
+    Ok(())
+}
+
+/// A menu's backups as `(timestamp, path)`, oldest first.
+pub fn list_backups(menu_path: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+    migrate_legacy_backups(menu_path)?;
+    let folder = backup_folder(menu_path)?;
+    if !folder.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut backups = vec![];
+    let entries = fs::read_dir(&folder)
+        .map_err(|e| io_err(format!("read_dir {} failed: {e}", fmt_path(&folder))))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| io_err(format!("dir entry error: {e}")))?;
+        let path = entry.path();
+        if path.extension().map(|x| x != "json").unwrap_or(true) {
+            continue;
+        }
+        let stamp = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        backups.push((stamp, path));
+    }
+    // By time, then by same-second counter, so `-10` comes after `-9`.
+    backups.sort_by(|(a, _), (b, _)| split_stamp(a).cmp(&split_stamp(b)).then(a.cmp(b)));
+    Ok(backups)
+}
+
+// ---------- History ----------
+#[derive(Serialize)]
+pub struct HistoryEntry {
+    pub timestamp: String,
+    pub time_ms: Option<i64>,
+    pub path: String,
+    /// None when the backup doesn't parse as a menu.
+    pub slot_count: Option<usize>,
+    /// Change from the previous readable version; the first one is compared
+    /// against an empty menu.
+    pub slot_delta: i64,
+    pub added_commands: Vec<String>,
+    pub removed_commands: Vec<String>,
+}
+
+fn command_ids(menu: &Value) -> BTreeSet<String> {
+    menu::items(menu)
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| menu::item_str(item, "command").to_string())
+                .filter(|c| !c.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Timestamps of the menu's saved versions, oldest first; each one can be
+/// passed to restore_menu_version.
+#[tauri::command]
+pub fn list_menu_history(path: String) -> Result<Vec<String>, String> {
+    Ok(list_backups(Path::new(&path))?
+        .into_iter()
+        .map(|(stamp, _)| stamp)
+        .collect())
+}
+
+/// The menu's backups as a timeline, oldest first, each with a short
+/// summary of how it differs from the version before it.
+#[tauri::command]
+pub fn menu_history(path: String) -> Result<Vec<HistoryEntry>, String> {
+    let mut history = vec![];
+    let mut prev_slots = 0usize;
+    let mut prev_ids = BTreeSet::new();
+    for (timestamp, file) in list_backups(Path::new(&path))? {
+        let menu = read_json_file(&file).ok();
+        let slot_count = menu
+            .as_ref()
+            .and_then(|m| menu::items(m).ok())
+            .map(Vec::len);
+        let mut entry = HistoryEntry {
+            time_ms: parse_timestamp(&timestamp),
+            timestamp,
+            path: fmt_path(&file),
+            slot_count,
+            slot_delta: 0,
+            added_commands: vec![],
+            removed_commands: vec![],
+        };
+        if let (Some(menu), Some(count)) = (&menu, slot_count) {
+            let ids = command_ids(menu);
+            entry.slot_delta = count as i64 - prev_slots as i64;
+            entry.added_commands = ids.difference(&prev_ids).cloned().collect();
+            entry.removed_commands = prev_ids.difference(&ids).cloned().collect();
+            prev_slots = count;
+            prev_ids = ids;
+        }
+        history.push(entry);
+    }
+    Ok(history)
+}
+
+/// Rolls the menu back to the backup taken at `backup_timestamp`. The
+/// current file is backed up first, so the rollback itself can be undone.
+#[tauri::command]
+pub fn restore_menu_version(path: String, backup_timestamp: String) -> Result<(), String> {
+    let menu_path = Path::new(&path);
+    let (_, file) = list_backups(menu_path)?
+        .into_iter()
+        .find(|(stamp, _)| *stamp == backup_timestamp)
+        .ok_or_else(|| io_err(format!("no backup of {path} taken at {backup_timestamp}")))?;
+    // Parse to make sure it's usable, but put back the exact bytes.
+    read_json_file(&file)?;
+    let bytes =
+        fs::read(&file).map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(&file))))?;
+    backup_existing(menu_path)?;
+    write_file_atomic(menu_path, &bytes)
+}
+
+// ---------- Compaction ----------
+/// Deletes backups identical to the one just before them, for every menu in
+/// the library. Older builds backed up on every save, changed or not; this
+/// clears out what they left. Returns how many files were removed.
+#[tauri::command]
+pub fn compact_backups(directory: String) -> Result<usize, String> {
+    let dir = Path::new(&directory);
+    let mut removed = 0;
+    for rel in library::menu_paths(dir, true)? {
+        let mut prev = None;
+        for (_, file) in list_backups(&dir.join(rel))? {
+            let hash = content_hash(&file)?;
+            if prev == Some(hash) {
+                fs::remove_file(&file)
+                    .map_err(|e| io_err(format!("delete {} failed: {e}", fmt_path(&file))))?;
+                removed += 1;
+            } else {
+                prev = Some(hash);
+            }
+        }
+    }
+    Ok(removed)
+}
+
+// ---------- Orphans ----------
+// Every folder in the library keeps its own `.history`, so one in a
+// subfolder belongs to menus in that subfolder.
+fn orphaned_backups(directory: &Path) -> Result<Vec<PathBuf>, String> {
+    if !directory.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut orphans = vec![];
+    for folder in library::menu_folders(directory)? {
+        for backup_dir in [HISTORY_DIR, LEGACY_BACKUP_DIR] {
+            orphans.extend(orphans_in(&folder, backup_dir)?);
+        }
+    }
+    orphans.sort();
+    Ok(orphans)
+}
+
+// `.history` folders are named by menu stem; legacy `.backups` ones by the
+// full file name.
+fn orphans_in(menu_dir: &Path, backup_dir: &str) -> Result<Vec<PathBuf>, String> {
+    let root = menu_dir.join(backup_dir);
+    if !root.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut orphans = vec![];
+    let entries = fs::read_dir(&root)
+        .map_err(|e| io_err(format!("read_dir {} failed: {e}", fmt_path(&root))))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| io_err(format!("dir entry error: {e}")))?;
+        let folder = entry.path();
+        let mut menu_name = entry.file_name();
+        if backup_dir == HISTORY_DIR {
+            menu_name.push(".json");
+        }
+        if !folder.is_dir() || menu_dir.join(menu_name).exists() {
+            continue;
+        }
+        let files = fs::read_dir(&folder)
+            .map_err(|e| io_err(format!("read_dir {} failed: {e}", fmt_path(&folder))))?;
+        for file in files {
+            let file = file.map_err(|e| io_err(format!("dir entry error: {e}")))?;
+            orphans.push(file.path());
+        }
+    }
+    Ok(orphans)
+}
+
+/// Backups whose menu is no longer in the library, from every folder's
+/// `.history` (and any `.backups` an older build left), subfolders included.
+#[tauri::command]
+pub fn find_orphaned_backups(directory: String) -> Result<Vec<String>, String> {
+    Ok(orphaned_backups(Path::new(&directory))?
+        .iter()
+        .map(|p| fmt_path(p))
+        .collect())
+}
+
+#[tauri::command]
+pub fn clean_orphaned_backups(directory: String) -> Result<usize, String> {
+    let orphans = orphaned_backups(Path::new(&directory))?;
+    for path in &orphans {
+        fs::remove_file(path)
+            .map_err(|e| io_err(format!("delete {} failed: {e}", fmt_path(path))))?;
+        // Drop the per-menu folder once it's empty; a non-empty one just stays.
+        if let Some(folder) = path.parent() {
+            let _ = fs::remove_dir(folder);
+        }
+    }
+    Ok(orphans.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_second_suffixes_keep_the_real_time_and_sort_in_order() {
+        let base = "2024-05-01T12-30-00";
+        assert!(parse_timestamp(base).is_some());
+        assert_eq!(
+            parse_timestamp("2024-05-01T12-30-00-2"),
+            parse_timestamp(base)
+        );
+
+        let mut stamps = vec![
+            "2024-05-01T12-30-00-10",
+            "2024-05-01T12-30-01",
+            "2024-05-01T12-30-00-2",
+            base,
+        ];
+        stamps.sort_by_key(|stamp| split_stamp(stamp));
+        assert_eq!(
+            stamps,
+            [
+                base,
+                "2024-05-01T12-30-00-2",
+                "2024-05-01T12-30-00-10",
+                "2024-05-01T12-30-01"
+            ]
+        );
+    }
+
+    #[test]
+    fn legacy_backups_move_into_history_and_orphans_are_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let menu = dir.path().join("Modeling.json");
+        fs::write(&menu, "{}").unwrap();
+        let legacy = dir.path().join(LEGACY_BACKUP_DIR).join("Modeling.json");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("2024-05-01T12-30-00.100Z.json"), "a").unwrap();
+        fs::write(legacy.join("2024-05-01T12-30-00.900Z.json"), "b").unwrap();
+        let gone = dir.path().join(LEGACY_BACKUP_DIR).join("Deleted.json");
+        fs::create_dir_all(&gone).unwrap();
+        fs::write(gone.join("2024-05-01T12-00-00.000Z.json"), "c").unwrap();
+
+        let stamps: Vec<String> = list_backups(&menu)
+            .unwrap()
+            .into_iter()
+            .map(|(stamp, _)| stamp)
+            .collect();
+        assert_eq!(stamps, ["2024-05-01T12-30-00", "2024-05-01T12-30-00-2"]);
+        assert!(!legacy.exists());
+
+        let orphans = orphaned_backups(dir.path()).unwrap();
+        assert_eq!(orphans, [gone.join("2024-05-01T12-00-00.000Z.json")]);
+    }
+}
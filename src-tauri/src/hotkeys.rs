@@ -0,0 +1,95 @@
+use std::path::Path;
+
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::{fmt_path, io_err, prefs};
+
+/// Emitted with the menu path when one of its hotkeys is pressed; the
+/// frontend loads the file in response.
+const OPEN_MENU_EVENT: &str = "open-menu";
+
+fn parse_accelerator(accelerator: &str) -> Result<Shortcut, String> {
+    accelerator.trim().parse::<Shortcut>().map_err(|e| {
+        io_err(format!(
+            "invalid accelerator {accelerator:?}: {e}; expected something like CmdOrCtrl+Shift+K"
+        ))
+    })
+}
+
+fn bind(app: &AppHandle, shortcut: Shortcut, path: String) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    // Rebinding the same keys to another menu replaces the old handler.
+    if shortcuts.is_registered(shortcut) {
+        shortcuts
+            .unregister(shortcut)
+            .map_err(|e| io_err(format!("unregister {shortcut} failed: {e}")))?;
+    }
+    shortcuts
+        .on_shortcut(shortcut, move |app, _, event| {
+            if event.state != ShortcutState::Pressed {
+                return;
+            }
+            if let Err(e) = app.emit(OPEN_MENU_EVENT, &path) {
+                tracing::warn!("emit {OPEN_MENU_EVENT} failed: {e}");
+            }
+        })
+        .map_err(|e| io_err(format!("register {shortcut} failed: {e}")))
+}
+
+/// Re-registers the hotkeys saved in preferences. Called from setup; a key
+/// another app already holds is logged and skipped rather than failing
+/// startup.
+pub fn restore(app: &AppHandle) {
+    let saved = prefs::active().menu_hotkeys;
+    for (accelerator, path) in saved {
+        let bound = parse_accelerator(&accelerator).and_then(|s| bind(app, s, path));
+        if let Err(e) = bound {
+            tracing::warn!("restore hotkey {accelerator} failed: {e}");
+        }
+    }
+}
+
+/// Binds a system-wide shortcut that emits `open-menu` with `path`, and saves
+/// it so it comes back on the next launch. Returns the accelerator in the
+/// parser's normalized spelling, which is what unregister_menu_hotkey and
+/// the preferences use.
+#[tauri::command]
+pub fn register_menu_hotkey(
+    path: String,
+    accelerator: String,
+    app: AppHandle,
+) -> Result<String, String> {
+    let shortcut = parse_accelerator(&accelerator)?;
+    if !Path::new(&path).is_file() {
+        return Err(io_err(format!(
+            "{} is not a menu file",
+            fmt_path(Path::new(&path))
+        )));
+    }
+    bind(&app, shortcut, path.clone())?;
+    let mut preferences = prefs::load(&app)?;
+    preferences.menu_hotkeys.insert(shortcut.to_string(), path);
+    prefs::save(&app, &preferences)?;
+    Ok(shortcut.to_string())
+}
+
+#[tauri::command]
+pub fn unregister_menu_hotkey(accelerator: String, app: AppHandle) -> Result<(), String> {
+    let shortcut = parse_accelerator(&accelerator)?;
+    let shortcuts = app.global_shortcut();
+    if shortcuts.is_registered(shortcut) {
+        shortcuts
+            .unregister(shortcut)
+            .map_err(|e| io_err(format!("unregister {shortcut} failed: {e}")))?;
+    }
+    let mut preferences = prefs::load(&app)?;
+    if preferences
+        .menu_hotkeys
+        .remove(&shortcut.to_string())
+        .is_some()
+    {
+        prefs::save(&app, &preferences)?;
+    }
+    Ok(())
+}
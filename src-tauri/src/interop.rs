@@ -0,0 +1,594 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{Read, Write},
+    path::Path,
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use tauri::Emitter;
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::{
+    backups, copy_mtime, fmt_path, io_err, library, menu, palette, read_json_file,
+    write_file_atomic, write_json_file,
+};
+
+#[derive(Serialize)]
+pub struct SkippedFile {
+    pub file: String,
+    pub reason: String,
+}
+
+/// What a bulk import wrote, and what it left behind and why.
+#[derive(Serialize, Default)]
+pub struct ImportReport {
+    pub imported: Vec<String>,
+    pub skipped: Vec<SkippedFile>,
+}
+
+// ---------- Combined library JSON ----------
+/// One JSON object mapping each menu's relative path to its content. Files
+/// that don't parse are skipped rather than failing the whole export.
+#[tauri::command]
+pub fn export_library_combined(directory: String, out_path: String) -> Result<(), String> {
+    let mut combined = Map::new();
+    for entry in library::load_library(Path::new(&directory))? {
+        if let Ok(menu) = entry.parsed {
+            combined.insert(entry.rel, menu);
+        }
+    }
+    write_json_file(Path::new(&out_path), &Value::Object(combined))
+}
+
+#[tauri::command]
+pub fn import_library_combined(
+    path: String,
+    dest_directory: String,
+) -> Result<Vec<String>, String> {
+    let Value::Object(combined) = read_json_file(Path::new(&path))? else {
+        return Err(io_err("combined library must be a JSON object"));
+    };
+    let dest = Path::new(&dest_directory);
+    // Resolve every path up front so one bad entry doesn't leave a
+    // half-written import behind.
+    let targets = combined
+        .keys()
+        .map(|rel| library::resolve_relative(dest, rel))
+        .collect::<Result<Vec<_>, _>>()?;
+    for (target, menu) in targets.iter().zip(combined.values()) {
+        backups::backup_existing(target)?;
+        write_json_file(target, menu)?;
+    }
+    Ok(combined.keys().cloned().collect())
+}
+
+/// import_library_combined with every menu validated first, so nothing is
+/// written unless the whole file is good. Existing files are backed up and
+/// replaced with `overwrite`, otherwise the import gets a suffixed name
+/// ("cut 2.json") next to them. Returns the paths written.
+#[tauri::command]
+pub fn explode_combined_library(
+    path: String,
+    dest_directory: String,
+    overwrite: bool,
+) -> Result<Vec<String>, String> {
+    let Value::Object(combined) = read_json_file(Path::new(&path))? else {
+        return Err(io_err("combined library must be a JSON object"));
+    };
+    let dest = Path::new(&dest_directory);
+    let mut errors = vec![];
+    let mut targets = vec![];
+    for (rel, menu) in &combined {
+        match library::resolve_relative(dest, rel) {
+            Ok(target) => targets.push(target),
+            Err(e) => errors.push(e),
+        }
+        if let Err(problems) = menu::validate_menu(menu) {
+            errors.push(format!("{rel}: {}", problems.join("; ")));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(io_err(errors.join("\n")));
+    }
+
+    let mut written = vec![];
+    for (target, menu) in targets.into_iter().zip(combined.values()) {
+        let target = match target.parent() {
+            Some(parent) if target.exists() && !overwrite => {
+                let stem = target.file_stem().unwrap_or_default().to_string_lossy();
+                library::unique_menu_path(parent, &stem)
+            }
+            _ => target,
+        };
+        backups::backup_existing(&target)?;
+        write_json_file(&target, menu)?;
+        written.push(fmt_path(&target));
+    }
+    Ok(written)
+}
+
+// ---------- Selective export ----------
+// `Acme-cut.json`, `acme_model.json` and `acme.json` all belong to project
+// "acme".
+fn has_project_prefix(rel: &str, project: &str) -> bool {
+    let stem = Path::new(rel)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let project = project.to_lowercase();
+    match stem.strip_prefix(&project) {
+        Some(rest) => rest.is_empty() || rest.starts_with(['-', '_', ' ', '.']),
+        None => false,
+    }
+}
+
+/// Copies the menus tagged `tag` in their top-level `tags`, or whose file
+/// name starts with it as a project prefix, into `out_directory` with their
+/// relative paths kept. Files that don't parse are logged and skipped.
+/// Returns the relative paths exported.
+#[tauri::command]
+pub fn export_menus_by_tag(
+    directory: String,
+    tag: String,
+    out_directory: String,
+) -> Result<Vec<String>, String> {
+    if tag.trim().is_empty() {
+        return Err(io_err("tag is empty"));
+    }
+    let dir = Path::new(&directory);
+    let out_dir = Path::new(&out_directory);
+    let mut exported = vec![];
+    for entry in library::load_library(dir)? {
+        let value = match entry.parsed {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::warn!("skipping {}: {e}", entry.rel);
+                continue;
+            }
+        };
+        if !menu::has_tag(&value, &tag) && !has_project_prefix(&entry.rel, &tag) {
+            continue;
+        }
+        let src = dir.join(&entry.rel);
+        let bytes =
+            fs::read(&src).map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(&src))))?;
+        write_file_atomic(&library::resolve_relative(out_dir, &entry.rel)?, &bytes)?;
+        exported.push(entry.rel);
+    }
+    Ok(exported)
+}
+
+// ---------- Folder import ----------
+/// Copies every valid menu from a folder the app doesn't manage into `dest`,
+/// suffixing names that are already taken ("cut 2.json").
+#[tauri::command]
+pub fn import_menus_from_directory(
+    src_directory: String,
+    dest_directory: String,
+    preserve_mtime: Option<bool>,
+) -> Result<ImportReport, String> {
+    let src = Path::new(&src_directory);
+    let dest = Path::new(&dest_directory);
+    let mut report = ImportReport::default();
+    for name in library::menu_file_names(src)? {
+        let path = src.join(&name);
+        let checked = fs::read(&path)
+            .map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(&path))))
+            .and_then(|bytes| {
+                let value: Value = serde_json::from_slice(&bytes)
+                    .map_err(|e| io_err(format!("parse failed: {e}")))?;
+                menu::validate_menu(&value).map_err(menu::validation_error)?;
+                Ok(bytes)
+            });
+        match checked {
+            Ok(bytes) => {
+                let target = library::unique_menu_path(dest, &name);
+                // Copy the original bytes so the import keeps its formatting.
+                write_file_atomic(&target, &bytes)?;
+                if preserve_mtime.unwrap_or(false) {
+                    copy_mtime(&path, &target)?;
+                }
+                report.imported.push(
+                    target
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned(),
+                );
+            }
+            Err(reason) => report.skipped.push(SkippedFile { file: name, reason }),
+        }
+    }
+    Ok(report)
+}
+
+// ---------- TOML ----------
+// JSON pointer to the first null, which TOML has no way to spell.
+fn first_null(value: &Value, at: &str) -> Option<String> {
+    match value {
+        Value::Null => Some(if at.is_empty() { "/".into() } else { at.into() }),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .find_map(|(i, v)| first_null(v, &format!("{at}/{i}"))),
+        Value::Object(fields) => fields
+            .iter()
+            .find_map(|(k, v)| first_null(v, &format!("{at}/{k}"))),
+        _ => None,
+    }
+}
+
+/// Writes the menu as TOML, slots as an `[[items]]` array of tables. JSON
+/// stays the canonical format; this is for repos that keep everything in
+/// TOML. TOML has no null, so a menu containing one is refused.
+#[tauri::command]
+pub fn export_radial_menu_toml(menu: Value, path: String) -> Result<(), String> {
+    menu::validate_menu(&menu).map_err(menu::validation_error)?;
+    if let Some(at) = first_null(&menu, "") {
+        return Err(io_err(format!(
+            "menu can't be written as TOML: {at} is null"
+        )));
+    }
+    let text = toml::to_string(&menu)
+        .map_err(|e| io_err(format!("menu can't be written as TOML: {e}")))?;
+    write_file_atomic(Path::new(&path), text.as_bytes())
+}
+
+#[tauri::command]
+pub fn import_radial_menu_toml(path: String) -> Result<Value, String> {
+    let path = Path::new(&path);
+    let text = fs::read_to_string(path)
+        .map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(path))))?;
+    let menu: Value = toml::from_str(&text)
+        .map_err(|e| io_err(format!("parse {} failed: {e}", fmt_path(path))))?;
+    menu::validate_menu(&menu).map_err(menu::validation_error)?;
+    Ok(menu)
+}
+
+// ---------- YAML ----------
+fn read_yaml_menu(path: &Path) -> Result<Value, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(path))))?;
+    let menu: Value = serde_yaml::from_str(&text)
+        .map_err(|e| io_err(format!("parse {} failed: {e}", fmt_path(path))))?;
+    menu::validate_menu(&menu).map_err(menu::validation_error)?;
+    Ok(menu)
+}
+
+#[tauri::command]
+pub fn import_radial_menu_yaml(path: String) -> Result<Value, String> {
+    read_yaml_menu(Path::new(&path))
+}
+
+fn is_yaml_file(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"))
+            .unwrap_or(false)
+}
+
+/// Converts every `.yaml`/`.yml` menu in `src_directory` (not recursive) to
+/// a `.json` in `dest_directory`, suffixing names that are already taken.
+#[tauri::command]
+pub fn convert_directory_yaml_to_json(
+    src_directory: String,
+    dest_directory: String,
+) -> Result<ImportReport, String> {
+    let src = Path::new(&src_directory);
+    let dest = Path::new(&dest_directory);
+    let listing =
+        fs::read_dir(src).map_err(|e| io_err(format!("read_dir {src_directory} failed: {e}")))?;
+    let mut paths = vec![];
+    for entry in listing {
+        let path = entry
+            .map_err(|e| io_err(format!("dir entry error: {e}")))?
+            .path();
+        if is_yaml_file(&path) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut report = ImportReport::default();
+    for path in paths {
+        let file = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        match read_yaml_menu(&path) {
+            Ok(menu) => {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                let target = library::unique_menu_path(dest, &stem);
+                write_json_file(&target, &menu)?;
+                report.imported.push(
+                    target
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned(),
+                );
+            }
+            Err(reason) => report.skipped.push(SkippedFile { file, reason }),
+        }
+    }
+    Ok(report)
+}
+
+// ---------- Command snapshot ----------
+const COMMAND_META_FIELD: &str = "_command_meta";
+
+/// A copy of the menu where every filled slot carries its command's merged
+/// commands entry under `_command_meta`, so the file still says what each
+/// slot did after commands.json moves on. Slots whose command isn't known
+/// get `{"unknown": true}`.
+#[tauri::command]
+pub fn snapshot_menu_with_metadata(
+    mut menu: Value,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    let commands = palette::merged_commands(&app)?;
+    for item in menu::items_mut(&mut menu)? {
+        if menu::is_empty_slot(item) {
+            continue;
+        }
+        let command = menu::item_str(item, "command");
+        let meta = match commands.get(command) {
+            Some(entry) => {
+                let mut meta = entry.as_object().cloned().unwrap_or_default();
+                meta.insert(
+                    "name".into(),
+                    Value::String(palette::entry_name(command, entry)),
+                );
+                Value::Object(meta)
+            }
+            None => serde_json::json!({ "unknown": true }),
+        };
+        if let Some(obj) = item.as_object_mut() {
+            obj.insert(COMMAND_META_FIELD.into(), meta);
+        }
+    }
+    Ok(menu)
+}
+
+// ---------- Release ----------
+// Layout: manifest.json, commands.json, README.md, menus/<relative path>.
+const RELEASE_MENUS: &str = "menus";
+
+/// Packages the library as `out_directory/<version>/`: every menu under
+/// `menus/`, a commands.json holding only the commands they use, a manifest
+/// with the version, date and each file's sha256, and a README listing the
+/// menus. An existing release folder is never written into. Returns the
+/// release folder.
+#[tauri::command]
+pub fn export_release(
+    directory: String,
+    version: String,
+    out_directory: String,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let version = version.trim();
+    let valid = !version.is_empty()
+        && !version.starts_with('.')
+        && !version.contains(['/', '\\'])
+        && library::sanitize_stem(version) == version;
+    if !valid {
+        return Err(io_err(format!("{version} can't be used as a folder name")));
+    }
+    let release = Path::new(&out_directory).join(version);
+    if release.exists() {
+        return Err(io_err(format!("{} already exists", fmt_path(&release))));
+    }
+
+    let dir = Path::new(&directory);
+    let menus_dir = release.join(RELEASE_MENUS);
+    let mut hashes = Map::new();
+    let mut readme =
+        format!("# Radial menus {version}\n\n| File | Name | Slots |\n| --- | --- | --- |\n");
+    for entry in library::load_library(dir)? {
+        let value = match entry.parsed {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::warn!("leaving {} out of the release: {e}", entry.rel);
+                continue;
+            }
+        };
+        let src = dir.join(&entry.rel);
+        let bytes =
+            fs::read(&src).map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(&src))))?;
+        write_file_atomic(&library::resolve_relative(&menus_dir, &entry.rel)?, &bytes)?;
+        let slots = menu::items(&value).map(Vec::len).unwrap_or(0);
+        let _ = writeln!(
+            readme,
+            "| {RELEASE_MENUS}/{} | {} | {slots} |",
+            entry.rel,
+            menu::item_str(&value, "name").replace('|', "\\|")
+        );
+        hashes.insert(
+            format!("{RELEASE_MENUS}/{}", entry.rel),
+            Value::String(format!("{:x}", Sha256::digest(&bytes))),
+        );
+    }
+
+    let commands = library::minimal_commands_for_directory(
+        directory.clone(),
+        app,
+        fmt_path(&release.join("commands.json")),
+    )?;
+    let manifest = serde_json::json!({
+        "version": version,
+        "date": &backups::timestamp_now()[..10],
+        "file_count": hashes.len(),
+        "command_count": commands,
+        "files": hashes,
+    });
+    write_json_file(&release.join("manifest.json"), &manifest)?;
+    write_file_atomic(&release.join("README.md"), readme.as_bytes())?;
+    Ok(fmt_path(&release))
+}
+
+// ---------- Translation ----------
+/// A flat `{"<slot index>": "<label>"}` file for translators. Slots without
+/// a label are left out; there's nothing to translate.
+#[tauri::command]
+pub fn export_labels_for_translation(menu: Value, out_path: String) -> Result<(), String> {
+    let labels: Map<String, Value> = menu::items(&menu)?
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| {
+            let label = menu::item_str(item, "label");
+            (!label.trim().is_empty()).then(|| (i.to_string(), Value::String(label.to_string())))
+        })
+        .collect();
+    write_json_file(Path::new(&out_path), &Value::Object(labels))
+}
+
+/// Writes translated labels back by slot index. Every key must be an index
+/// into the menu and every value a string; all problems are reported
+/// together and nothing is applied unless the whole map is good.
+#[tauri::command]
+pub fn apply_translated_labels(mut menu: Value, map: Value) -> Result<Value, String> {
+    let Value::Object(map) = map else {
+        return Err(io_err("translations must be a JSON object"));
+    };
+    let items = menu::items_mut(&mut menu)?;
+    let mut errors = vec![];
+    let mut updates = vec![];
+    for (key, label) in map {
+        let index = match key.parse::<usize>() {
+            Ok(i) if i < items.len() => i,
+            Ok(_) => {
+                errors.push(format!("{key}: no such slot (menu has {})", items.len()));
+                continue;
+            }
+            Err(_) => {
+                errors.push(format!("{key}: not a slot index"));
+                continue;
+            }
+        };
+        match label {
+            Value::String(label) => updates.push((index, label)),
+            _ => errors.push(format!("{key}: label must be a string")),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(io_err(format!(
+            "invalid translations: {}",
+            errors.join("; ")
+        )));
+    }
+    for (index, label) in updates {
+        if let Some(obj) = items[index].as_object_mut() {
+            obj.insert("label".into(), Value::String(label));
+        }
+    }
+    Ok(menu)
+}
+
+// ---------- Share links ----------
+const SHARE_LINK_PREFIX: &str = "radialbuilder://import";
+/// Emitted with the decoded menu when the app is opened through a share link.
+const IMPORT_LINK_EVENT: &str = "import-link";
+/// Longest `data` value accepted either way; links much past this get
+/// mangled or refused by chat clients anyway.
+const MAX_LINK_DATA: usize = 32 * 1024;
+/// Cap on the inflated JSON, so a small link can't expand into a huge one.
+const MAX_LINK_MENU_BYTES: u64 = 1024 * 1024;
+
+/// The menu as `radialbuilder://import?data=...`: compact JSON, deflated,
+/// then base64url without padding.
+#[tauri::command]
+pub fn menu_to_share_link(menu: Value) -> Result<String, String> {
+    menu::validate_menu(&menu).map_err(menu::validation_error)?;
+    let json =
+        serde_json::to_vec(&menu).map_err(|e| io_err(format!("serialize menu failed: {e}")))?;
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder
+        .write_all(&json)
+        .map_err(|e| io_err(format!("compress menu failed: {e}")))?;
+    let compressed = encoder
+        .finish()
+        .map_err(|e| io_err(format!("compress menu failed: {e}")))?;
+    let data = URL_SAFE_NO_PAD.encode(compressed);
+    if data.len() > MAX_LINK_DATA {
+        return Err(io_err(format!(
+            "menu is too large for a share link ({} characters, limit {MAX_LINK_DATA})",
+            data.len()
+        )));
+    }
+    Ok(format!("{SHARE_LINK_PREFIX}?data={data}"))
+}
+
+#[tauri::command]
+pub fn menu_from_share_link(url: String) -> Result<Value, String> {
+    let query = url
+        .trim()
+        .strip_prefix(SHARE_LINK_PREFIX)
+        .and_then(|rest| rest.trim_start_matches('/').strip_prefix('?'))
+        .ok_or_else(|| {
+            io_err(format!(
+                "not a share link; expected {SHARE_LINK_PREFIX}?data=..."
+            ))
+        })?;
+    let data = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("data="))
+        .ok_or_else(|| io_err("share link has no data"))?;
+    if data.len() > MAX_LINK_DATA {
+        return Err(io_err("share link data is too large"));
+    }
+    let compressed = URL_SAFE_NO_PAD
+        .decode(data)
+        .map_err(|e| io_err(format!("share link data is corrupt: {e}")))?;
+    let mut json = vec![];
+    DeflateDecoder::new(compressed.as_slice())
+        .take(MAX_LINK_MENU_BYTES + 1)
+        .read_to_end(&mut json)
+        .map_err(|e| io_err(format!("share link data is corrupt: {e}")))?;
+    if json.len() as u64 > MAX_LINK_MENU_BYTES {
+        return Err(io_err("share link menu is too large"));
+    }
+    let menu: Value = serde_json::from_slice(&json)
+        .map_err(|e| io_err(format!("share link menu does not parse: {e}")))?;
+    menu::validate_menu(&menu).map_err(menu::validation_error)?;
+    Ok(menu)
+}
+
+fn emit_share_link(app: &tauri::AppHandle, url: &str) {
+    // A bad link has already been logged by menu_from_share_link.
+    let Ok(menu) = menu_from_share_link(url.to_string()) else {
+        return;
+    };
+    if let Err(e) = app.emit(IMPORT_LINK_EVENT, menu) {
+        tracing::warn!("emit {IMPORT_LINK_EVENT} failed: {e}");
+    }
+}
+
+/// Called from setup: handles the link the app was launched with, if any,
+/// and any opened while it runs.
+pub fn listen_for_share_links(app: &tauri::AppHandle) {
+    let deep_link = app.deep_link();
+    // macOS registers the scheme from the bundle; elsewhere make sure an
+    // unpackaged build is registered too.
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = deep_link.register_all() {
+        tracing::warn!("register share link scheme failed: {e}");
+    }
+    if let Ok(Some(urls)) = deep_link.get_current() {
+        for url in urls {
+            emit_share_link(app, url.as_str());
+        }
+    }
+    let handle = app.clone();
+    deep_link.on_open_url(move |event| {
+        for url in event.urls() {
+            emit_share_link(&handle, url.as_str());
+        }
+    });
+}
@@ -0,0 +1,320 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write,
+    path::Path,
+};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{io_err, library, menu, palette, write_file_atomic};
+
+#[derive(Serialize)]
+pub struct SliceKey {
+    pub index: usize,
+    pub label: String,
+    pub command: String,
+    pub key: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct KeyConflict {
+    pub key: String,
+    pub slots: Vec<usize>,
+}
+
+#[derive(Serialize)]
+pub struct KeyHit {
+    pub file: String,
+    pub labels: Vec<String>,
+}
+
+// ---------- Resolution ----------
+/// A slot's own `keybinding` wins; otherwise it inherits the command's
+/// default from the merged commands.
+pub fn effective_keys(
+    menu: &Value,
+    commands: &Map<String, Value>,
+) -> Result<Vec<SliceKey>, String> {
+    let items = menu::items(menu)?;
+    Ok(items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let command = menu::item_str(item, "command");
+            let key = match menu::item_str(item, "keybinding") {
+                "" => commands
+                    .get(command)
+                    .and_then(palette::entry_keybinding)
+                    .map(str::to_string),
+                own => Some(own.to_string()),
+            };
+            SliceKey {
+                index,
+                label: menu::item_str(item, "label").to_string(),
+                command: command.to_string(),
+                key,
+            }
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn resolve_effective_keybindings(
+    menu: Value,
+    app: tauri::AppHandle,
+) -> Result<Vec<SliceKey>, String> {
+    let commands = palette::merged_commands(&app)?;
+    effective_keys(&menu, &commands)
+}
+
+// ---------- Conflicts ----------
+/// Keys compare case-insensitively and ignore spacing, so `Ctrl + K` and
+/// `ctrl+k` count as the same binding.
+pub fn normalize_key(key: &str) -> String {
+    key.split_whitespace().collect::<String>().to_lowercase()
+}
+
+/// Effective keys bound to more than one slot.
+pub fn keybinding_conflicts(
+    menu: &Value,
+    commands: &Map<String, Value>,
+) -> Result<Vec<KeyConflict>, String> {
+    let mut by_key: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for slot in effective_keys(menu, commands)? {
+        if let Some(key) = slot.key {
+            by_key
+                .entry(normalize_key(&key))
+                .or_default()
+                .push(slot.index);
+        }
+    }
+    Ok(by_key
+        .into_iter()
+        .filter(|(_, slots)| slots.len() > 1)
+        .map(|(key, slots)| KeyConflict { key, slots })
+        .collect())
+}
+
+#[tauri::command]
+pub fn find_keybinding_conflicts(
+    menu: Value,
+    app: tauri::AppHandle,
+) -> Result<Vec<KeyConflict>, String> {
+    let commands = palette::merged_commands(&app)?;
+    keybinding_conflicts(&menu, &commands)
+}
+
+// ---------- Drift ----------
+#[derive(Serialize)]
+pub struct DriftEntry {
+    pub index: usize,
+    pub label: String,
+    pub command: String,
+    pub override_key: String,
+    pub default_key: String,
+}
+
+/// Slots whose own `keybinding` disagrees with their command's current
+/// default; after a commands update these may no longer be intended. Slots
+/// overriding a command with no default aren't drift.
+#[tauri::command]
+pub fn find_override_drift(menu: Value, app: tauri::AppHandle) -> Result<Vec<DriftEntry>, String> {
+    let commands = palette::merged_commands(&app)?;
+    let mut drift = vec![];
+    for (index, item) in menu::items(&menu)?.iter().enumerate() {
+        let own = menu::item_str(item, "keybinding");
+        let command = menu::item_str(item, "command");
+        let Some(default) = commands.get(command).and_then(palette::entry_keybinding) else {
+            continue;
+        };
+        if own.trim().is_empty() || normalize_key(own) == normalize_key(default) {
+            continue;
+        }
+        drift.push(DriftEntry {
+            index,
+            label: menu::item_str(item, "label").to_string(),
+            command: command.to_string(),
+            override_key: own.to_string(),
+            default_key: default.to_string(),
+        });
+    }
+    Ok(drift)
+}
+
+// ---------- Assignment ----------
+#[derive(Serialize)]
+pub struct AutoAssigned {
+    pub menu: Value,
+    /// Set when some slots were left unbound because the candidates ran out.
+    pub note: Option<String>,
+}
+
+/// Gives each filled slot without an effective key the next candidate that
+/// nothing in the menu uses yet. Existing bindings, own or inherited from the
+/// command, are left alone.
+#[tauri::command]
+pub fn auto_assign_keys(
+    mut menu: Value,
+    candidate_keys: Vec<String>,
+    app: tauri::AppHandle,
+) -> Result<AutoAssigned, String> {
+    let commands = palette::merged_commands(&app)?;
+    let slots = effective_keys(&menu, &commands)?;
+    let mut used: HashSet<String> = slots
+        .iter()
+        .filter_map(|slot| slot.key.as_deref().map(normalize_key))
+        .collect();
+    let mut candidates = candidate_keys
+        .into_iter()
+        .filter(|key| !key.trim().is_empty());
+    let mut unbound = 0;
+    let items = menu::items_mut(&mut menu)?;
+    for slot in slots {
+        if slot.key.is_some() || slot.command.is_empty() {
+            continue;
+        }
+        let next = candidates.find(|key| !used.contains(&normalize_key(key)));
+        match next {
+            Some(key) => {
+                used.insert(normalize_key(&key));
+                if let Some(obj) = items[slot.index].as_object_mut() {
+                    obj.insert("keybinding".into(), Value::String(key));
+                }
+            }
+            None => unbound += 1,
+        }
+    }
+    Ok(AutoAssigned {
+        menu,
+        note: (unbound > 0)
+            .then(|| format!("ran out of candidate keys; {unbound} slots left unbound")),
+    })
+}
+
+// ---------- Library lookup ----------
+/// Every menu in the library that binds `key`, with the labels of the slots
+/// that do. Menus that fail to parse are skipped; verify_directory reports
+/// those.
+#[tauri::command]
+pub fn find_menus_using_key(
+    directory: String,
+    key: String,
+    app: tauri::AppHandle,
+) -> Result<Vec<KeyHit>, String> {
+    let wanted = normalize_key(&key);
+    let commands = palette::merged_commands(&app)?;
+    let mut hits = vec![];
+    for entry in library::load_library(Path::new(&directory))? {
+        let Ok(menu) = entry.parsed else {
+            continue;
+        };
+        let Ok(slots) = effective_keys(&menu, &commands) else {
+            continue;
+        };
+        let labels: Vec<String> = slots
+            .into_iter()
+            .filter(|slot| slot.key.as_deref().map(normalize_key).as_deref() == Some(&wanted))
+            .map(|slot| slot.label)
+            .collect();
+        if !labels.is_empty() {
+            hits.push(KeyHit {
+                file: entry.rel,
+                labels,
+            });
+        }
+    }
+    Ok(hits)
+}
+
+// ---------- Cheat sheet ----------
+fn md_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn html_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// One section per menu file, its slots in ring order with their effective
+/// keys. `format` is "markdown" or "html".
+#[tauri::command]
+pub fn export_keybinding_cheatsheet(
+    directory: String,
+    out_path: String,
+    format: String,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    let html = match format.as_str() {
+        "markdown" => false,
+        "html" => true,
+        other => {
+            return Err(io_err(format!(
+                "unknown cheat sheet format {other}; use markdown or html"
+            )))
+        }
+    };
+    let commands = palette::merged_commands(&app)?;
+    let mut out = String::new();
+    if html {
+        out.push_str("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Radial menu keybindings</title></head><body>\n<h1>Radial menu keybindings</h1>\n");
+    } else {
+        out.push_str("# Radial menu keybindings\n");
+    }
+    for entry in library::load_library(Path::new(&directory))? {
+        let Ok(menu) = entry.parsed else {
+            continue;
+        };
+        let Ok(slots) = effective_keys(&menu, &commands) else {
+            continue;
+        };
+        let name = menu::item_str(&menu, "name");
+        let title = if name.is_empty() {
+            entry.rel.clone()
+        } else {
+            format!("{name} ({})", entry.rel)
+        };
+        let filled = slots.into_iter().filter(|slot| !slot.command.is_empty());
+        if html {
+            let _ = writeln!(
+                out,
+                "<h2>{}</h2>\n<table>\n<tr><th>Slot</th><th>Label</th><th>Command</th><th>Key</th></tr>",
+                html_text(&title)
+            );
+            for slot in filled {
+                let _ = writeln!(
+                    out,
+                    "<tr><td>{}</td><td>{}</td><td><code>{}</code></td><td>{}</td></tr>",
+                    slot.index,
+                    html_text(&slot.label),
+                    html_text(&slot.command),
+                    html_text(slot.key.as_deref().unwrap_or(""))
+                );
+            }
+            out.push_str("</table>\n");
+        } else {
+            let _ = writeln!(
+                out,
+                "\n## {}\n\n| Slot | Label | Command | Key |\n| --- | --- | --- | --- |",
+                md_cell(&title)
+            );
+            for slot in filled {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | `{}` | {} |",
+                    slot.index,
+                    md_cell(&slot.label),
+                    md_cell(&slot.command),
+                    md_cell(slot.key.as_deref().unwrap_or(""))
+                );
+            }
+        }
+    }
+    if html {
+        out.push_str("</body></html>\n");
+    }
+    write_file_atomic(Path::new(&out_path), out.as_bytes())
+}
@@ -0,0 +1,1672 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tauri::Manager;
+
+use crate::{
+    app_data_dir, backups, fmt_path, io_err, keys, menu, palette, read_json_file,
+    write_file_atomic, write_json_file,
+};
+
+pub const IGNORE_FILE: &str = ".radialignore";
+
+// ---------- Ignore rules ----------
+/// Glob patterns from the directory's `.radialignore`, one per line. Blank
+/// lines and `#` comments are skipped; a missing file excludes nothing.
+pub fn load_ignore(dir: &Path) -> Result<GlobSet, String> {
+    let path = dir.join(IGNORE_FILE);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(GlobSet::empty()),
+        Err(e) => return Err(io_err(format!("read {} failed: {e}", fmt_path(&path)))),
+    };
+
+    let mut builder = GlobSetBuilder::new();
+    for (n, line) in text.lines().enumerate() {
+        let pattern = line.trim();
+        if pattern.is_empty() || pattern.starts_with('#') {
+            continue;
+        }
+        let glob = Glob::new(pattern)
+            .map_err(|e| io_err(format!("{} line {}: {e}", fmt_path(&path), n + 1)))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| io_err(format!("build {} patterns failed: {e}", fmt_path(&path))))
+}
+
+// ---------- Listing ----------
+/// Deep enough for any sane project layout while keeping a pathological
+/// tree from hanging the app.
+const MAX_DEPTH: usize = 16;
+
+/// `.json` menus under `dir` as forward-slash paths relative to it, minus
+/// ignored ones, sorted. Hidden folders (including `.history`) are skipped.
+pub fn menu_paths(dir: &Path, recursive: bool) -> Result<Vec<String>, String> {
+    if !dir.exists() {
+        return Err(io_err(format!(
+            "directory {} does not exist",
+            fmt_path(dir)
+        )));
+    }
+    let ignore = load_ignore(dir)?;
+    let mut files = vec![];
+    let mut visited = HashSet::new();
+    walk(dir, "", recursive, 0, &ignore, &mut visited, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn walk(
+    dir: &Path,
+    prefix: &str,
+    recursive: bool,
+    depth: usize,
+    ignore: &GlobSet,
+    visited: &mut HashSet<PathBuf>,
+    files: &mut Vec<String>,
+) -> Result<(), String> {
+    // Symlinked folders can point back up the tree; only enter each real
+    // directory once.
+    if let Ok(real) = fs::canonicalize(dir) {
+        if !visited.insert(real) {
+            return Ok(());
+        }
+    }
+    for entry in
+        fs::read_dir(dir).map_err(|e| io_err(format!("read_dir {} failed: {e}", fmt_path(dir))))?
+    {
+        let entry = entry.map_err(|e| io_err(format!("dir entry error: {e}")))?;
+        let path = entry.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let rel = format!("{prefix}{name}");
+        if path.is_dir() {
+            if recursive && depth < MAX_DEPTH && !name.starts_with('.') {
+                walk(
+                    &path,
+                    &format!("{rel}/"),
+                    recursive,
+                    depth + 1,
+                    ignore,
+                    visited,
+                    files,
+                )?;
+            }
+        } else if path.extension().map(|x| x == "json").unwrap_or(false) && !ignore.is_match(&rel) {
+            files.push(rel);
+        }
+    }
+    Ok(())
+}
+
+/// `dir` and every folder under it that menu_paths would descend into,
+/// sorted. Same depth limit and hidden-folder and symlink rules.
+pub fn menu_folders(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    if !dir.exists() {
+        return Err(io_err(format!(
+            "directory {} does not exist",
+            fmt_path(dir)
+        )));
+    }
+    let mut folders = vec![];
+    let mut visited = HashSet::new();
+    walk_folders(dir, 0, &mut visited, &mut folders)?;
+    folders.sort();
+    Ok(folders)
+}
+
+fn walk_folders(
+    dir: &Path,
+    depth: usize,
+    visited: &mut HashSet<PathBuf>,
+    folders: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    if let Ok(real) = fs::canonicalize(dir) {
+        if !visited.insert(real) {
+            return Ok(());
+        }
+    }
+    folders.push(dir.to_path_buf());
+    if depth >= MAX_DEPTH {
+        return Ok(());
+    }
+    for entry in
+        fs::read_dir(dir).map_err(|e| io_err(format!("read_dir {} failed: {e}", fmt_path(dir))))?
+    {
+        let entry = entry.map_err(|e| io_err(format!("dir entry error: {e}")))?;
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if path.is_dir() && !hidden {
+            walk_folders(&path, depth + 1, visited, folders)?;
+        }
+    }
+    Ok(())
+}
+
+/// Top-level menu names, in the order list_json_files shows them, so indexes
+/// into it are stable for the UI.
+pub fn menu_file_names(dir: &Path) -> Result<Vec<String>, String> {
+    menu_paths(dir, false)
+}
+
+/// A library menu with its parse result, so health checks can report broken
+/// files instead of aborting on the first one.
+pub struct LibraryMenu {
+    pub rel: String,
+    pub parsed: Result<Value, String>,
+}
+
+/// Every menu in the library, recursively, backups excluded.
+pub fn load_library(dir: &Path) -> Result<Vec<LibraryMenu>, String> {
+    Ok(menu_paths(dir, true)?
+        .into_iter()
+        .map(|rel| {
+            let parsed = read_json_file(&dir.join(&rel));
+            LibraryMenu { rel, parsed }
+        })
+        .collect())
+}
+
+/// Resolves a relative menu path from an import against `dest`, refusing
+/// anything that would land outside it.
+pub fn resolve_relative(dest: &Path, rel: &str) -> Result<PathBuf, String> {
+    let rel_path = Path::new(rel);
+    let escapes = rel_path
+        .components()
+        .any(|c| !matches!(c, std::path::Component::Normal(_)));
+    if rel.is_empty() || escapes {
+        return Err(io_err(format!("unsafe relative path {rel}")));
+    }
+    if rel_path.extension().map(|x| x != "json").unwrap_or(true) {
+        return Err(io_err(format!("{rel} is not a .json file")));
+    }
+    Ok(dest.join(rel_path))
+}
+
+// ---------- Naming ----------
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// A file stem that is safe on Windows, macOS and Linux alike: characters
+/// any of them reject become `-`, trailing dots and spaces are dropped (Windows
+/// strips them silently) and reserved device names get a `_` prefix.
+pub fn sanitize_stem(name: &str) -> String {
+    let name = name.trim();
+    let name = name.strip_suffix(".json").unwrap_or(name);
+    let mut out: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .collect();
+    while out.ends_with(['.', ' ']) {
+        out.pop();
+    }
+    if out.is_empty() {
+        return "menu".into();
+    }
+    let device = out.split('.').next().unwrap_or("").to_ascii_uppercase();
+    if RESERVED_NAMES.contains(&device.as_str()) {
+        out.insert(0, '_');
+    }
+    out
+}
+
+#[tauri::command]
+pub fn sanitize_menu_filename(name: String) -> String {
+    format!("{}.json", sanitize_stem(&name))
+}
+
+#[derive(Serialize)]
+pub struct UnsafeName {
+    pub file: String,
+    pub suggested: String,
+}
+
+/// Menus whose file name sanitize_menu_filename would change, i.e. ones that
+/// some OS would reject or mangle, each with the name it suggests instead.
+#[tauri::command]
+pub fn find_unsafe_filenames(directory: String) -> Result<Vec<UnsafeName>, String> {
+    Ok(menu_paths(Path::new(&directory), true)?
+        .into_iter()
+        .filter_map(|rel| {
+            let (folder, name) = match rel.rsplit_once('/') {
+                Some((folder, name)) => (format!("{folder}/"), name),
+                None => (String::new(), rel.as_str()),
+            };
+            let safe = sanitize_menu_filename(name.to_string());
+            (safe != name).then(|| UnsafeName {
+                suggested: format!("{folder}{safe}"),
+                file: rel.clone(),
+            })
+        })
+        .collect())
+}
+
+/// Top-level menu file names that don't fully match `pattern`, a regex such
+/// as `[a-z]+_[a-z]+_[a-z]+\.json`. The pattern is anchored for you, so
+/// it has to describe the whole name.
+#[tauri::command]
+pub fn check_naming_policy(directory: String, pattern: String) -> Result<Vec<String>, String> {
+    let policy = regex::Regex::new(&format!("^(?:{pattern})$"))
+        .map_err(|e| io_err(format!("invalid naming pattern: {e}")))?;
+    Ok(menu_file_names(Path::new(&directory))?
+        .into_iter()
+        .filter(|name| !policy.is_match(name))
+        .collect())
+}
+
+#[derive(Serialize)]
+pub struct TitleMismatch {
+    pub file: String,
+    pub name: String,
+    pub stem: String,
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Menus whose `name` doesn't match their file name. A name only counts as
+/// matching when sanitizing it gives exactly the file's stem, so
+/// `Cut / Extrude` matches `Cut - Extrude.json`. Menus without a name, and
+/// files that don't parse, are skipped.
+#[tauri::command]
+pub fn find_title_filename_mismatches(directory: String) -> Result<Vec<TitleMismatch>, String> {
+    let mut mismatches = vec![];
+    for LibraryMenu { rel, parsed } in load_library(Path::new(&directory))? {
+        let Ok(value) = parsed else { continue };
+        let name = menu::item_str(&value, "name");
+        if name.trim().is_empty() {
+            continue;
+        }
+        let stem = file_stem(Path::new(&rel));
+        if sanitize_stem(name) != stem {
+            mismatches.push(TitleMismatch {
+                name: name.to_string(),
+                file: rel,
+                stem,
+            });
+        }
+    }
+    Ok(mismatches)
+}
+
+/// Sets the menu's `name` to its file stem, backing the file up first.
+/// Returns the new name.
+#[tauri::command]
+pub fn sync_title_to_filename(path: String) -> Result<String, String> {
+    let path = Path::new(&path);
+    let mut value = read_json_file(path)?;
+    let stem = file_stem(path);
+    let Some(obj) = value.as_object_mut() else {
+        return Err(io_err(format!("{} is not a menu object", fmt_path(path))));
+    };
+    if obj.get("name").and_then(Value::as_str) != Some(stem.as_str()) {
+        obj.insert("name".into(), Value::String(stem.clone()));
+        backups::backup_existing(path)?;
+        write_json_file(path, &value)?;
+    }
+    Ok(stem)
+}
+
+/// `base.json`, else `base 2.json`, `base 3.json`, ... whichever is free.
+pub fn unique_menu_path(dir: &Path, base: &str) -> PathBuf {
+    let stem = sanitize_stem(base);
+    let mut candidate = dir.join(format!("{stem}.json"));
+    let mut n = 2;
+    while candidate.exists() {
+        candidate = dir.join(format!("{stem} {n}.json"));
+        n += 1;
+    }
+    candidate
+}
+
+#[tauri::command]
+pub fn suggest_menu_name(directory: String, base: String) -> Result<String, String> {
+    let dir = Path::new(&directory);
+    if !dir.is_dir() {
+        return Err(io_err(format!("directory {directory} does not exist")));
+    }
+    let path = unique_menu_path(dir, &base);
+    Ok(path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default())
+}
+
+/// Re-sorts names with the locale's collation rules (so German umlauts sit
+/// next to their base letters). An unparseable or unsupported locale leaves
+/// the default byte order alone.
+pub fn collate(names: &mut [String], locale: &str) {
+    let Ok(locale) = locale.parse::<icu_locid::Locale>() else {
+        return;
+    };
+    let Ok(collator) =
+        icu_collator::Collator::try_new(&(&locale).into(), icu_collator::CollatorOptions::new())
+    else {
+        return;
+    };
+    names.sort_by(|a, b| collator.compare(a, b));
+}
+
+// ---------- Fingerprint ----------
+/// A cheap change signal for polling: hashes each menu's relative path, size
+/// and mtime, so any add, remove or edit changes the result.
+#[tauri::command]
+pub fn directory_fingerprint(directory: String, recursive: Option<bool>) -> Result<String, String> {
+    let dir = Path::new(&directory);
+    let mut hasher = Sha256::new();
+    for rel in menu_paths(dir, recursive.unwrap_or(false))? {
+        let path = dir.join(&rel);
+        let meta = fs::metadata(&path)
+            .map_err(|e| io_err(format!("stat {} failed: {e}", fmt_path(&path))))?;
+        let mtime = crate::file_mtime_ms(&path)?.unwrap_or_default();
+        hasher.update(format!("{rel}\0{}\0{mtime}\n", meta.len()));
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[tauri::command]
+pub fn load_radial_menu_by_index(directory: String, index: usize) -> Result<Value, String> {
+    let dir = Path::new(&directory);
+    let names = menu_file_names(dir)?;
+    let name = names.get(index).ok_or_else(|| {
+        io_err(format!(
+            "menu index {index} is out of range ({} menus in {directory})",
+            names.len()
+        ))
+    })?;
+    crate::read_menu(&dir.join(name))
+}
+
+// ---------- Verification ----------
+#[derive(Serialize)]
+pub struct FileReport {
+    pub file: String,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct VerifyReport {
+    pub passed: bool,
+    pub files: Vec<FileReport>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Serialize)]
+pub struct LintIssue {
+    /// Stable identifier for tooling: parse-error, invalid-menu,
+    /// unknown-command or key-conflict.
+    pub code: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl LintIssue {
+    fn new(code: &'static str, severity: Severity, message: String) -> Self {
+        LintIssue {
+            code,
+            severity,
+            message,
+        }
+    }
+}
+
+/// Parse and structure problems are errors; unknown commands and key
+/// conflicts are warnings since the menu still loads in Plasticity.
+fn lint_menu(
+    parsed: Result<Value, String>,
+    commands: &serde_json::Map<String, Value>,
+) -> Result<Vec<LintIssue>, String> {
+    let value = match parsed {
+        Ok(value) => value,
+        Err(e) => return Ok(vec![LintIssue::new("parse-error", Severity::Error, e)]),
+    };
+    if let Err(errors) = menu::validate_menu(&value) {
+        return Ok(errors
+            .into_iter()
+            .map(|e| LintIssue::new("invalid-menu", Severity::Error, e))
+            .collect());
+    }
+    let mut issues = vec![];
+    for id in palette::unknown_command_ids(&value, commands)? {
+        issues.push(LintIssue::new(
+            "unknown-command",
+            Severity::Warning,
+            format!("unknown command id {id}"),
+        ));
+    }
+    for conflict in keys::keybinding_conflicts(&value, commands)? {
+        let slots: Vec<String> = conflict.slots.iter().map(|s| s.to_string()).collect();
+        issues.push(LintIssue::new(
+            "key-conflict",
+            Severity::Warning,
+            format!(
+                "key {} is bound by slots {}",
+                conflict.key,
+                slots.join(", ")
+            ),
+        ));
+    }
+    Ok(issues)
+}
+
+fn lint_library(
+    dir: &Path,
+    app: &tauri::AppHandle,
+) -> Result<Vec<(String, Vec<LintIssue>)>, String> {
+    let commands = palette::merged_commands(app)?;
+    load_library(dir)?
+        .into_iter()
+        .map(|LibraryMenu { rel, parsed }| Ok((rel, lint_menu(parsed, &commands)?)))
+        .collect()
+}
+
+/// Pre-flight lint for a whole library.
+#[tauri::command]
+pub fn verify_directory(directory: String, app: tauri::AppHandle) -> Result<VerifyReport, String> {
+    let files: Vec<FileReport> = lint_library(Path::new(&directory), &app)?
+        .into_iter()
+        .map(|(file, issues)| {
+            let (errors, warnings): (Vec<_>, Vec<_>) = issues
+                .into_iter()
+                .partition(|issue| issue.severity == Severity::Error);
+            FileReport {
+                file,
+                errors: errors.into_iter().map(|i| i.message).collect(),
+                warnings: warnings.into_iter().map(|i| i.message).collect(),
+            }
+        })
+        .collect();
+    Ok(VerifyReport {
+        passed: files.iter().all(|f| f.errors.is_empty()),
+        files,
+    })
+}
+
+/// verify_directory's findings as plain JSON for CI: every issue keeps its
+/// code and severity, plus overall error and warning counts.
+#[tauri::command]
+pub fn lint_directory_json(directory: String, app: tauri::AppHandle) -> Result<Value, String> {
+    let files = lint_library(Path::new(&directory), &app)?;
+    let count = |severity: Severity| {
+        files
+            .iter()
+            .flat_map(|(_, issues)| issues)
+            .filter(|issue| issue.severity == severity)
+            .count()
+    };
+    let (error_count, warning_count) = (count(Severity::Error), count(Severity::Warning));
+    let files: Vec<Value> = files
+        .into_iter()
+        .map(|(file, issues)| serde_json::json!({ "file": file, "issues": issues }))
+        .collect();
+    Ok(serde_json::json!({
+        "directory": directory,
+        "error_count": error_count,
+        "warning_count": warning_count,
+        "files": files,
+    }))
+}
+
+#[derive(Serialize)]
+pub struct MenuCompat {
+    /// Sorted; empty for the group that works with the current commands.
+    pub missing: Vec<String>,
+    pub menus: Vec<String>,
+}
+
+/// Menus bucketed by exactly which merged-command ids they lack, so menus
+/// that break for the same reason sit together. Groups run from fewest
+/// missing ids to most; menus that don't parse are skipped.
+#[tauri::command]
+pub fn group_menus_by_missing_commands(
+    directory: String,
+    app: tauri::AppHandle,
+) -> Result<Vec<MenuCompat>, String> {
+    let commands = palette::merged_commands(&app)?;
+    let mut groups: BTreeMap<Vec<String>, Vec<String>> = BTreeMap::new();
+    for LibraryMenu { rel, parsed } in load_library(Path::new(&directory))? {
+        let Ok(value) = parsed else {
+            continue;
+        };
+        let Ok(mut missing) = palette::unknown_command_ids(&value, &commands) else {
+            continue;
+        };
+        missing.sort();
+        groups.entry(missing).or_default().push(rel);
+    }
+    let mut out: Vec<MenuCompat> = groups
+        .into_iter()
+        .map(|(missing, menus)| MenuCompat { missing, menus })
+        .collect();
+    out.sort_by_key(|group| group.missing.len());
+    Ok(out)
+}
+
+// ---------- Usage ----------
+#[derive(Serialize)]
+pub struct CommandUsage {
+    pub command: String,
+    /// Slots using the command across the whole library.
+    pub count: usize,
+    pub menus: Vec<String>,
+}
+
+/// How often each command appears across the library, most used first
+/// (ties by id). Nested-menu links and empty slots don't count.
+pub fn usage_stats(dir: &Path) -> Result<Vec<CommandUsage>, String> {
+    let mut usage: BTreeMap<String, CommandUsage> = BTreeMap::new();
+    for LibraryMenu { rel, parsed } in load_library(dir)? {
+        let Ok(value) = parsed else {
+            continue;
+        };
+        let Ok(items) = menu::items(&value) else {
+            continue;
+        };
+        for item in items {
+            let id = menu::item_str(item, "command");
+            if id.is_empty() || id.starts_with(palette::NESTED_MENU_PREFIX) {
+                continue;
+            }
+            let entry = usage.entry(id.to_string()).or_insert_with(|| CommandUsage {
+                command: id.to_string(),
+                count: 0,
+                menus: vec![],
+            });
+            entry.count += 1;
+            if !entry.menus.contains(&rel) {
+                entry.menus.push(rel.clone());
+            }
+        }
+    }
+    let mut out: Vec<CommandUsage> = usage.into_values().collect();
+    out.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.command.cmp(&b.command))
+    });
+    Ok(out)
+}
+
+#[tauri::command]
+pub fn command_usage_stats(directory: String) -> Result<Vec<CommandUsage>, String> {
+    usage_stats(Path::new(&directory))
+}
+
+/// Writes a commands file holding only the merged entries the library's
+/// menus use, in merged order, for shipping alongside them. Used ids with no
+/// entry are logged and left out. Returns how many entries were written.
+#[tauri::command]
+pub fn minimal_commands_for_directory(
+    directory: String,
+    app: tauri::AppHandle,
+    out_path: String,
+) -> Result<usize, String> {
+    let used: HashSet<String> = usage_stats(Path::new(&directory))?
+        .into_iter()
+        .map(|usage| usage.command)
+        .collect();
+    let merged = palette::merged_commands(&app)?;
+    let mut missing: Vec<&str> = used
+        .iter()
+        .map(String::as_str)
+        .filter(|id| !merged.contains_key(*id))
+        .collect();
+    if !missing.is_empty() {
+        missing.sort_unstable();
+        tracing::warn!(
+            "used but not in the commands set, skipped: {}",
+            missing.join(", ")
+        );
+    }
+    let minimal: serde_json::Map<String, Value> = merged
+        .into_iter()
+        .filter(|(id, _)| used.contains(id))
+        .collect();
+    let count = minimal.len();
+    write_json_file(Path::new(&out_path), &Value::Object(minimal))?;
+    Ok(count)
+}
+
+/// A sensible first ring for someone whose library has nothing to learn
+/// from yet, roughly in order of how often modelers reach for them.
+const STARTER_COMMANDS: [&str; 12] = [
+    "command:move",
+    "command:rotate",
+    "command:scale",
+    "command:extrude",
+    "command:fillet",
+    "command:boolean",
+    "command:cut",
+    "command:mirror",
+    "command:offset-curve",
+    "command:duplicate",
+    "command:loft",
+    "command:delete",
+];
+
+/// A new menu of the library's `slice_count` most-used commands, topped up
+/// from STARTER_COMMANDS when the library uses fewer than that.
+#[tauri::command]
+pub fn suggest_starter_menu(
+    directory: String,
+    slice_count: u32,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    let count = slice_count as usize;
+    if !(menu::MIN_SLOTS..=menu::MAX_SLOTS).contains(&count) {
+        return Err(io_err(format!(
+            "slice_count must be between {} and {}",
+            menu::MIN_SLOTS,
+            menu::MAX_SLOTS
+        )));
+    }
+    let commands = palette::merged_commands(&app)?;
+    let mut ids: Vec<String> = usage_stats(Path::new(&directory))?
+        .into_iter()
+        .map(|usage| usage.command)
+        .take(count)
+        .collect();
+    for id in STARTER_COMMANDS {
+        if ids.len() >= count {
+            break;
+        }
+        if !ids.iter().any(|used| used == id) {
+            ids.push(id.to_string());
+        }
+    }
+    let items: Vec<Value> = ids.iter().map(|id| command_slot(id, &commands)).collect();
+    Ok(serde_json::json!({
+        "name": "Starter",
+        "command": "default-menu:starter",
+        "items": items,
+    }))
+}
+
+// A slot for `id`, labelled and iconed from its commands entry when it has
+// one.
+fn command_slot(id: &str, commands: &serde_json::Map<String, Value>) -> Value {
+    let (label, icon) = match commands.get(id) {
+        Some(entry) => (
+            palette::entry_name(id, entry),
+            palette::entry_icon(entry).unwrap_or("").to_string(),
+        ),
+        None => (palette::label_from_command(id), String::new()),
+    };
+    serde_json::json!({ "command": id, "icon": icon, "label": label })
+}
+
+/// Lays a list of command ids out on a ring of `ring_size` slots, in order,
+/// padding the rest with empty slots. A blank id leaves a gap. Every other
+/// id must be a known command, and the list must fit on the ring.
+#[tauri::command]
+pub fn build_menu_from_command_list(
+    ids: Vec<String>,
+    ring_size: u32,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    let size = ring_size as usize;
+    if !(menu::MIN_SLOTS..=menu::MAX_SLOTS).contains(&size) {
+        return Err(io_err(format!(
+            "ring_size must be between {} and {}",
+            menu::MIN_SLOTS,
+            menu::MAX_SLOTS
+        )));
+    }
+    if ids.len() > size {
+        return Err(io_err(format!(
+            "{} commands don't fit on a ring of {size}",
+            ids.len()
+        )));
+    }
+    let commands = palette::merged_commands(&app)?;
+    let unknown: Vec<&str> = ids
+        .iter()
+        .map(|id| id.trim())
+        .filter(|id| !id.is_empty() && !commands.contains_key(*id))
+        .collect();
+    if !unknown.is_empty() {
+        return Err(io_err(format!("unknown commands: {}", unknown.join(", "))));
+    }
+    let mut items: Vec<Value> = ids
+        .iter()
+        .map(|id| match id.trim() {
+            "" => menu::empty_slot(),
+            id => command_slot(id, &commands),
+        })
+        .collect();
+    items.resize_with(size, menu::empty_slot);
+    Ok(serde_json::json!({
+        "name": "Commands",
+        "command": "default-menu:commands",
+        "items": items,
+    }))
+}
+
+// A fresh random u64 per call; RandomState is seeded from the OS, which is
+// plenty for a throwaway menu and saves pulling in a RNG crate.
+fn random_u64() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
+}
+
+// One key per slot, in ring order, so a generated menu never conflicts.
+const TEST_KEYS: [&str; 12] = ["1", "2", "3", "4", "5", "6", "7", "8", "9", "0", "-", "="];
+
+/// A throwaway menu of `slice_count` random merged commands, each labelled
+/// and bound to its own key, for QA and demos.
+#[tauri::command]
+pub fn generate_test_menu(slice_count: u32, app: tauri::AppHandle) -> Result<Value, String> {
+    let count = slice_count as usize;
+    if !(menu::MIN_SLOTS..=menu::MAX_SLOTS).contains(&count) {
+        return Err(io_err(format!(
+            "slice_count must be between {} and {}",
+            menu::MIN_SLOTS,
+            menu::MAX_SLOTS
+        )));
+    }
+    let commands = palette::merged_commands(&app)?;
+    let mut pool: Vec<(&String, &Value)> = commands.iter().collect();
+    if pool.len() < count {
+        return Err(io_err(format!(
+            "only {} commands available, {count} requested",
+            pool.len()
+        )));
+    }
+    // Partial Fisher-Yates: the first `count` entries end up a random pick.
+    for i in 0..count {
+        let j = i + (random_u64() % (pool.len() - i) as u64) as usize;
+        pool.swap(i, j);
+    }
+    let items: Vec<Value> = pool[..count]
+        .iter()
+        .zip(TEST_KEYS)
+        .map(|((id, entry), key)| {
+            serde_json::json!({
+                "command": id,
+                "icon": palette::entry_icon(entry).unwrap_or(""),
+                "label": palette::entry_name(id, entry),
+                "keybinding": key,
+            })
+        })
+        .collect();
+    let menu = serde_json::json!({
+        "name": "Test menu",
+        "command": "default-menu:test",
+        "items": items,
+    });
+    menu::validate_menu(&menu).map_err(menu::validation_error)?;
+    Ok(menu)
+}
+
+// ---------- Empty menus ----------
+#[derive(Serialize)]
+pub struct EmptyMenus {
+    pub empty: Vec<String>,
+    /// Files that couldn't be read as a menu at all, kept apart so they
+    /// aren't mistaken for empty ones.
+    pub unparseable: Vec<String>,
+}
+
+/// Menus where no slot has a command or a label, typically placeholders
+/// left behind while authoring.
+#[tauri::command]
+pub fn find_empty_menus(directory: String) -> Result<EmptyMenus, String> {
+    let mut report = EmptyMenus {
+        empty: vec![],
+        unparseable: vec![],
+    };
+    for LibraryMenu { rel, parsed } in load_library(Path::new(&directory))? {
+        let value = parsed.unwrap_or(Value::Null);
+        let Ok(items) = menu::items(&value) else {
+            report.unparseable.push(rel);
+            continue;
+        };
+        let blank = items.iter().all(|item| {
+            menu::is_empty_slot(item) && menu::item_str(item, "label").trim().is_empty()
+        });
+        if blank {
+            report.empty.push(rel);
+        }
+    }
+    Ok(report)
+}
+
+// ---------- Count limit ----------
+#[derive(Serialize, Clone)]
+pub struct MenuCountStatus {
+    pub count: usize,
+    pub max: usize,
+    pub within: bool,
+}
+
+/// Top-level menus only, since those are what Plasticity's picker lists.
+pub fn menu_count_status(dir: &Path, max: usize) -> Result<MenuCountStatus, String> {
+    let count = menu_file_names(dir)?.len();
+    Ok(MenuCountStatus {
+        count,
+        max,
+        within: count <= max,
+    })
+}
+
+#[tauri::command]
+pub fn check_menu_count_limit(directory: String, max: usize) -> Result<MenuCountStatus, String> {
+    menu_count_status(Path::new(&directory), max)
+}
+
+// ---------- Ring sizes ----------
+#[derive(Serialize)]
+pub struct RingSizeCount {
+    pub size: usize,
+    pub count: usize,
+    pub menus: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct RingSizes {
+    /// Smallest ring first.
+    pub sizes: Vec<RingSizeCount>,
+    pub unparseable: Vec<String>,
+}
+
+/// How many menus use each ring size, so a stray 12-slot menu in a library
+/// of 8s stands out.
+#[tauri::command]
+pub fn ring_size_distribution(directory: String) -> Result<RingSizes, String> {
+    let mut by_size: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    let mut unparseable = vec![];
+    for LibraryMenu { rel, parsed } in load_library(Path::new(&directory))? {
+        let value = parsed.unwrap_or(Value::Null);
+        match menu::items(&value) {
+            Ok(items) => by_size.entry(items.len()).or_default().push(rel),
+            Err(_) => unparseable.push(rel),
+        }
+    }
+    Ok(RingSizes {
+        sizes: by_size
+            .into_iter()
+            .map(|(size, menus)| RingSizeCount {
+                size,
+                count: menus.len(),
+                menus,
+            })
+            .collect(),
+        unparseable,
+    })
+}
+
+// ---------- Label consistency ----------
+#[derive(Serialize)]
+pub struct LabelUse {
+    pub file: String,
+    pub slot: usize,
+}
+
+#[derive(Serialize)]
+pub struct LabelVariant {
+    pub label: String,
+    pub uses: Vec<LabelUse>,
+}
+
+#[derive(Serialize)]
+pub struct LabelCluster {
+    /// The trimmed, lowercased form every variant shares.
+    pub key: String,
+    pub variants: Vec<LabelVariant>,
+}
+
+/// Labels that read the same once trimmed and case-folded but are spelled
+/// differently somewhere in the library ("Fillet", "fillet", "Fillet ").
+#[tauri::command]
+pub fn find_label_inconsistencies(directory: String) -> Result<Vec<LabelCluster>, String> {
+    let mut by_key: BTreeMap<String, BTreeMap<String, Vec<LabelUse>>> = BTreeMap::new();
+    for LibraryMenu { rel, parsed } in load_library(Path::new(&directory))? {
+        let Ok(value) = parsed else {
+            continue;
+        };
+        let Ok(items) = menu::items(&value) else {
+            continue;
+        };
+        for (slot, item) in items.iter().enumerate() {
+            let label = menu::item_str(item, "label");
+            let key = label.trim().to_lowercase();
+            if key.is_empty() {
+                continue;
+            }
+            by_key
+                .entry(key)
+                .or_default()
+                .entry(label.to_string())
+                .or_default()
+                .push(LabelUse {
+                    file: rel.clone(),
+                    slot,
+                });
+        }
+    }
+    Ok(by_key
+        .into_iter()
+        .filter(|(_, variants)| variants.len() > 1)
+        .map(|(key, variants)| LabelCluster {
+            key,
+            variants: variants
+                .into_iter()
+                .map(|(label, uses)| LabelVariant { label, uses })
+                .collect(),
+        })
+        .collect())
+}
+
+// ---------- Stale menus ----------
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Serialize)]
+pub struct StaleMenu {
+    pub file: String,
+    pub modified_ms: i64,
+    pub age_days: u64,
+}
+
+#[derive(Serialize)]
+pub struct StaleMenus {
+    pub stale: Vec<StaleMenu>,
+    /// Files whose mtime couldn't be used, kept apart so they aren't taken
+    /// for fresh ones.
+    pub skipped: Vec<String>,
+}
+
+/// Menus not modified in more than `older_than_days`, oldest first. Files
+/// whose mtime can't be trusted (unreadable, at or before the epoch, or in
+/// the future, as some network and FAT volumes report) are listed in
+/// `skipped` rather than guessed at.
+#[tauri::command]
+pub fn find_stale_menus(directory: String, older_than_days: u64) -> Result<StaleMenus, String> {
+    let dir = Path::new(&directory);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    let mut stale = vec![];
+    let mut skipped = vec![];
+    for rel in menu_paths(dir, true)? {
+        let modified = match crate::file_mtime_ms(&dir.join(&rel)) {
+            Ok(Some(ms)) if ms > 0 && ms <= now => ms,
+            _ => {
+                skipped.push(rel);
+                continue;
+            }
+        };
+        let age_days = ((now - modified) / DAY_MS) as u64;
+        if age_days > older_than_days {
+            stale.push(StaleMenu {
+                file: rel,
+                modified_ms: modified,
+                age_days,
+            });
+        }
+    }
+    stale.sort_by_key(|menu| menu.modified_ms);
+    Ok(StaleMenus { stale, skipped })
+}
+
+// ---------- Load benchmark ----------
+const SLOWEST_FILES: usize = 5;
+
+#[derive(Serialize)]
+pub struct FileTiming {
+    pub file: String,
+    pub ms: f64,
+}
+
+#[derive(Serialize)]
+pub struct LoadBench {
+    pub commands_ms: f64,
+    pub list_ms: f64,
+    /// Reading and parsing every listed menu, back to back.
+    pub parse_ms: f64,
+    pub file_count: usize,
+    pub slowest: Vec<FileTiming>,
+}
+
+fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Times the startup path: merging the commands, listing the library and
+/// reading and parsing each menu. Read-only; a file that fails to parse is
+/// timed like any other.
+#[tauri::command]
+pub fn benchmark_load(directory: String, app: tauri::AppHandle) -> Result<LoadBench, String> {
+    let dir = Path::new(&directory);
+
+    let start = Instant::now();
+    palette::merged_commands(&app)?;
+    let commands_ms = elapsed_ms(start);
+
+    let start = Instant::now();
+    let files = menu_paths(dir, true)?;
+    let list_ms = elapsed_ms(start);
+
+    let start = Instant::now();
+    let mut timings: Vec<FileTiming> = files
+        .into_iter()
+        .map(|rel| {
+            let file_start = Instant::now();
+            let _ = read_json_file(&dir.join(&rel));
+            FileTiming {
+                file: rel,
+                ms: elapsed_ms(file_start),
+            }
+        })
+        .collect();
+    let parse_ms = elapsed_ms(start);
+
+    let file_count = timings.len();
+    timings.sort_by(|a, b| b.ms.total_cmp(&a.ms));
+    timings.truncate(SLOWEST_FILES);
+    Ok(LoadBench {
+        commands_ms,
+        list_ms,
+        parse_ms,
+        file_count,
+        slowest: timings,
+    })
+}
+
+#[derive(Serialize)]
+pub struct FileLoadTime {
+    pub file: String,
+    pub bytes: u64,
+    /// Reading and parsing are timed apart, so a slow disk and a parse
+    /// pathology look different.
+    pub read_ms: f64,
+    pub parse_ms: f64,
+    pub total_ms: f64,
+    pub error: Option<String>,
+}
+
+/// Every menu's load time on its own, slowest first. Read-only.
+#[tauri::command]
+pub fn profile_directory_loads(directory: String) -> Result<Vec<FileLoadTime>, String> {
+    let dir = Path::new(&directory);
+    let mut times: Vec<FileLoadTime> = menu_paths(dir, true)?
+        .into_iter()
+        .map(|rel| {
+            let start = Instant::now();
+            let read = fs::read(dir.join(&rel));
+            let read_ms = elapsed_ms(start);
+            let (bytes, parse_ms, error) = match read {
+                Ok(data) => {
+                    let start = Instant::now();
+                    let parsed = serde_json::from_slice::<Value>(&data);
+                    let error = parsed.err().map(|e| format!("parse failed: {e}"));
+                    (data.len() as u64, elapsed_ms(start), error)
+                }
+                Err(e) => (0, 0.0, Some(format!("read failed: {e}"))),
+            };
+            FileLoadTime {
+                file: rel,
+                bytes,
+                read_ms,
+                parse_ms,
+                total_ms: read_ms + parse_ms,
+                error,
+            }
+        })
+        .collect();
+    times.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+    Ok(times)
+}
+
+// ---------- Notes ----------
+/// Checked in this order; new notes go in the first.
+const NOTES_FILES: [&str; 2] = ["README.md", "notes.txt"];
+
+fn notes_path(dir: &Path) -> Option<PathBuf> {
+    NOTES_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+#[tauri::command]
+pub fn load_directory_notes(directory: String) -> Result<Option<String>, String> {
+    let Some(path) = notes_path(Path::new(&directory)) else {
+        return Ok(None);
+    };
+    fs::read_to_string(&path)
+        .map(Some)
+        .map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(&path))))
+}
+
+/// Overwrites whichever notes file the directory already has, or starts a
+/// README.md.
+#[tauri::command]
+pub fn save_directory_notes(directory: String, text: String) -> Result<(), String> {
+    let dir = Path::new(&directory);
+    if !dir.is_dir() {
+        return Err(io_err(format!("directory {directory} does not exist")));
+    }
+    let path = notes_path(dir).unwrap_or_else(|| dir.join(NOTES_FILES[0]));
+    write_file_atomic(&path, text.as_bytes())
+}
+
+// ---------- Bundled examples ----------
+// Shipped under the app's resources; there is deliberately no command that
+// writes here, users "save as" into their own directory instead.
+fn examples_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let base = app
+        .path()
+        .resource_dir()
+        .map_err(|e| io_err(format!("resource_dir error: {e}")))?;
+    Ok(base.join("examples"))
+}
+
+#[tauri::command]
+pub fn list_example_menus(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    menu_file_names(&examples_dir(&app)?)
+}
+
+#[tauri::command]
+pub fn load_example_menu(name: String, app: tauri::AppHandle) -> Result<Value, String> {
+    // Names come from list_example_menus; anything path-like is refused so
+    // this can't be used to read outside the examples folder.
+    if name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(io_err(format!("invalid example name {name}")));
+    }
+    read_json_file(&examples_dir(&app)?.join(name))
+}
+
+// ---------- Maintenance ----------
+/// Rewrites every menu that uses `old_id` to use `new_id` instead, each one
+/// backed up and written atomically. With `dry_run` nothing is written and
+/// the result is just the files that would change.
+#[tauri::command]
+pub fn replace_command_everywhere(
+    directory: String,
+    old_id: String,
+    new_id: String,
+    dry_run: bool,
+) -> Result<Vec<String>, String> {
+    let dir = Path::new(&directory);
+    let mut affected = vec![];
+    for LibraryMenu { rel, parsed } in load_library(dir)? {
+        let Ok(mut value) = parsed else { continue };
+        if menu::replace_command(&mut value, &old_id, &new_id) == 0 {
+            continue;
+        }
+        if !dry_run {
+            let path = dir.join(&rel);
+            backups::backup_existing(&path)?;
+            write_json_file(&path, &value)?;
+        }
+        affected.push(rel);
+    }
+    Ok(affected)
+}
+
+#[derive(Serialize)]
+pub struct RenameReport {
+    pub overlay_entries: usize,
+    pub menu_slices: usize,
+    /// Menus that were, or with `dry_run` would be, rewritten.
+    pub files: Vec<String>,
+}
+
+/// replace_command_everywhere plus the overlay, so a renamed command keeps
+/// its customizations. Refused when `new_id` is already a command. Only the
+/// overlay is touched on the commands side: a base entry under `old_id`
+/// stays there, and the overlay's fields move to `new_id`.
+#[tauri::command]
+pub fn rename_command(
+    old_id: String,
+    new_id: String,
+    directory: String,
+    app: tauri::AppHandle,
+    dry_run: bool,
+) -> Result<RenameReport, String> {
+    if new_id.trim().is_empty() {
+        return Err(io_err("new command id is empty"));
+    }
+    if palette::merged_commands(&app)?.contains_key(&new_id) {
+        return Err(io_err(format!("command {new_id} already exists")));
+    }
+    let overlay_entries = palette::rename_overlay_id(&app, &old_id, &new_id, dry_run)?;
+
+    let dir = Path::new(&directory);
+    let mut menu_slices = 0;
+    let mut files = vec![];
+    for LibraryMenu { rel, parsed } in load_library(dir)? {
+        let Ok(mut value) = parsed else { continue };
+        let changed = menu::replace_command(&mut value, &old_id, &new_id);
+        if changed == 0 {
+            continue;
+        }
+        if !dry_run {
+            let path = dir.join(&rel);
+            backups::backup_existing(&path)?;
+            write_json_file(&path, &value)?;
+        }
+        menu_slices += changed;
+        files.push(rel);
+    }
+    Ok(RenameReport {
+        overlay_entries,
+        menu_slices,
+        files,
+    })
+}
+
+#[derive(Serialize)]
+pub struct ImpactEntry {
+    pub file: String,
+    pub slots: Vec<usize>,
+    pub labels: Vec<String>,
+    /// Set for files that couldn't be checked; they might use the command.
+    pub error: Option<String>,
+}
+
+/// Every menu with a slot bound to `command_id`, to check before dropping
+/// the command from the overlay. Backups aren't part of the library listing.
+#[tauri::command]
+pub fn command_impact(command_id: String, directory: String) -> Result<Vec<ImpactEntry>, String> {
+    let mut impact = vec![];
+    for LibraryMenu { rel, parsed } in load_library(Path::new(&directory))? {
+        let items = parsed.and_then(|value| menu::items(&value).cloned());
+        let items = match items {
+            Ok(items) => items,
+            Err(e) => {
+                impact.push(ImpactEntry {
+                    file: rel,
+                    slots: vec![],
+                    labels: vec![],
+                    error: Some(e),
+                });
+                continue;
+            }
+        };
+        let (slots, labels): (Vec<usize>, Vec<String>) = items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| menu::item_str(item, "command") == command_id)
+            .map(|(i, item)| (i, menu::item_str(item, "label").to_string()))
+            .unzip();
+        if !slots.is_empty() {
+            impact.push(ImpactEntry {
+                file: rel,
+                slots,
+                labels,
+                error: None,
+            });
+        }
+    }
+    Ok(impact)
+}
+
+const DEPRECATED_FILE: &str = "deprecated.json";
+
+#[derive(Serialize)]
+pub struct DeprecatedSlot {
+    pub index: usize,
+    pub label: String,
+    pub command: String,
+    pub replacement: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DeprecationHit {
+    pub file: String,
+    pub slots: Vec<DeprecatedSlot>,
+    /// Set for files that couldn't be checked.
+    pub error: Option<String>,
+}
+
+// app_data_dir/deprecated.json: either a list of ids or an object mapping
+// each deprecated id to its replacement (null when there is none).
+fn load_deprecated(app: &tauri::AppHandle) -> Result<BTreeMap<String, Option<String>>, String> {
+    let path = app_data_dir(app)?.join(DEPRECATED_FILE);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    match read_json_file(&path)? {
+        Value::Array(ids) => Ok(ids
+            .iter()
+            .filter_map(Value::as_str)
+            .filter(|id| !id.trim().is_empty())
+            .map(|id| (id.to_string(), None))
+            .collect()),
+        Value::Object(map) => Ok(map
+            .into_iter()
+            .map(|(id, to)| (id, to.as_str().map(str::to_string)))
+            .collect()),
+        _ => Err(io_err(format!(
+            "{} must be a list of ids or an object of id: replacement",
+            fmt_path(&path)
+        ))),
+    }
+}
+
+/// Menus with slots still bound to a deprecated command. `deprecated` lists
+/// the ids to look for; left empty, they come from deprecated.json in
+/// app data, whose mapping (if it has one) also supplies replacements.
+#[tauri::command]
+pub fn find_deprecated_commands(
+    directory: String,
+    deprecated: Vec<String>,
+    app: tauri::AppHandle,
+) -> Result<Vec<DeprecationHit>, String> {
+    let mut wanted = load_deprecated(&app)?;
+    if !deprecated.is_empty() {
+        wanted = deprecated
+            .into_iter()
+            .filter(|id| !id.trim().is_empty())
+            .map(|id| {
+                let replacement = wanted.get(&id).cloned().flatten();
+                (id, replacement)
+            })
+            .collect();
+    }
+    if wanted.is_empty() {
+        return Err(io_err(format!(
+            "no deprecated ids given and no {DEPRECATED_FILE} in app data"
+        )));
+    }
+
+    let mut hits = vec![];
+    for LibraryMenu { rel, parsed } in load_library(Path::new(&directory))? {
+        let items = match parsed.and_then(|value| menu::items(&value).cloned()) {
+            Ok(items) => items,
+            Err(e) => {
+                hits.push(DeprecationHit {
+                    file: rel,
+                    slots: vec![],
+                    error: Some(e),
+                });
+                continue;
+            }
+        };
+        let slots: Vec<DeprecatedSlot> = items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let command = menu::item_str(item, "command");
+                let replacement = wanted.get(command)?;
+                Some(DeprecatedSlot {
+                    index,
+                    label: menu::item_str(item, "label").to_string(),
+                    command: command.to_string(),
+                    replacement: replacement.clone(),
+                })
+            })
+            .collect();
+        if !slots.is_empty() {
+            hits.push(DeprecationHit {
+                file: rel,
+                slots,
+                error: None,
+            });
+        }
+    }
+    Ok(hits)
+}
+
+#[derive(Serialize)]
+pub struct DedupeReport {
+    /// How much smaller the icon files on disk got: the duplicates that were
+    /// removed, less any canonical copies written to `icons_dir`.
+    pub bytes_saved: u64,
+    pub references_updated: usize,
+    /// Canonical icons under `icons_dir` that menus now point at.
+    pub canonical_files: Vec<String>,
+    /// Menus that were rewritten.
+    pub files: Vec<String>,
+}
+
+// The icon file a slot refers to, if there is one: relative icons resolve
+// against the menu's folder. Icon names that aren't files yield None.
+fn icon_file(menu_dir: &Path, icon: &str) -> Option<PathBuf> {
+    if icon.trim().is_empty() {
+        return None;
+    }
+    let path = menu_dir.join(icon);
+    path.is_file()
+        .then(|| fs::canonicalize(&path).unwrap_or(path))
+}
+
+// Forward-slash and relative to the menu's folder when the icon is under
+// it, absolute otherwise.
+fn icon_reference(menu_dir: &Path, icon: &Path) -> String {
+    let base = fs::canonicalize(menu_dir).unwrap_or_else(|_| menu_dir.to_path_buf());
+    match icon.strip_prefix(&base) {
+        Ok(rel) => rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => icon.to_string_lossy().into_owned(),
+    }
+}
+
+// Where a group of identical icons settles: a member already in icons_dir,
+// else the first one's file name there, suffixed with the hash if that name
+// is taken by different content. Also says whether a new file was written.
+fn canonical_icon(
+    icons_dir: &Path,
+    members: &[PathBuf],
+    hash: &str,
+    bytes: &[u8],
+) -> Result<(PathBuf, bool), String> {
+    if let Some(existing) = members.iter().find(|p| p.parent() == Some(icons_dir)) {
+        return Ok((existing.clone(), false));
+    }
+    let first = &members[0];
+    let mut target = icons_dir.join(first.file_name().unwrap_or_default());
+    if target.exists() && fs::read(&target).ok().as_deref() != Some(bytes) {
+        let stem = first.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match first.extension() {
+            Some(ext) => format!("{stem}-{}.{}", &hash[..8], ext.to_string_lossy()),
+            None => format!("{stem}-{}", &hash[..8]),
+        };
+        target = icons_dir.join(name);
+    }
+    if target.exists() {
+        return Ok((target, false));
+    }
+    write_file_atomic(&target, bytes)?;
+    Ok((target, true))
+}
+
+/// Finds icon files that menus reference under different paths but with
+/// identical content, copies one of each into `icons_dir` and points every
+/// reference at that copy. Menus are backed up before they're rewritten.
+/// Once every menu is written, the duplicates inside `directory` are
+/// removed; ones outside it may belong to something else and stay. Icons
+/// that only ever appear under one path are left where they are.
+#[tauri::command]
+pub fn dedupe_icons(directory: String, icons_dir: String) -> Result<DedupeReport, String> {
+    let dir = Path::new(&directory);
+    fs::create_dir_all(&icons_dir)
+        .map_err(|e| io_err(format!("create {icons_dir} failed: {e}")))?;
+    let icons_dir = fs::canonicalize(&icons_dir)
+        .map_err(|e| io_err(format!("resolve {icons_dir} failed: {e}")))?;
+
+    let menus: Vec<(String, Value)> = load_library(dir)?
+        .into_iter()
+        .filter_map(|LibraryMenu { rel, parsed }| Some((rel, parsed.ok()?)))
+        .collect();
+
+    // Every distinct icon file, grouped by content.
+    let mut by_hash: BTreeMap<String, (Vec<u8>, Vec<PathBuf>)> = BTreeMap::new();
+    let mut seen = HashSet::new();
+    for (rel, value) in &menus {
+        let path = dir.join(rel);
+        let menu_dir = path.parent().unwrap_or(dir);
+        for item in menu::items(value).map(Vec::as_slice).unwrap_or_default() {
+            let Some(icon) = icon_file(menu_dir, menu::item_str(item, "icon")) else {
+                continue;
+            };
+            if !seen.insert(icon.clone()) {
+                continue;
+            }
+            let bytes = fs::read(&icon)
+                .map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(&icon))))?;
+            let hash = format!("{:x}", Sha256::digest(&bytes));
+            by_hash
+                .entry(hash)
+                .or_insert_with(|| (bytes, vec![]))
+                .1
+                .push(icon);
+        }
+    }
+
+    let mut bytes_written = 0;
+    let mut canonical_files = vec![];
+    let mut redirect: BTreeMap<PathBuf, (PathBuf, u64)> = BTreeMap::new();
+    for (hash, (bytes, members)) in &by_hash {
+        if members.len() < 2 {
+            continue;
+        }
+        let (target, written) = canonical_icon(&icons_dir, members, hash, bytes)?;
+        if written {
+            bytes_written += bytes.len() as u64;
+        }
+        for member in members {
+            if *member != target {
+                redirect.insert(member.clone(), (target.clone(), bytes.len() as u64));
+            }
+        }
+        canonical_files.push(fmt_path(&target));
+    }
+
+    let mut references_updated = 0;
+    let mut files = vec![];
+    for (rel, mut value) in menus {
+        let path = dir.join(&rel);
+        let menu_dir = path.parent().unwrap_or(dir).to_path_buf();
+        let mut changed = 0;
+        // A JSON file that isn't a menu has no icons to point anywhere.
+        let Ok(items) = menu::items_mut(&mut value) else {
+            continue;
+        };
+        for item in items {
+            let Some(icon) = icon_file(&menu_dir, menu::item_str(item, "icon")) else {
+                continue;
+            };
+            if let Some((target, _)) = redirect.get(&icon) {
+                item["icon"] = Value::String(icon_reference(&menu_dir, target));
+                changed += 1;
+            }
+        }
+        if changed == 0 {
+            continue;
+        }
+        backups::backup_existing(&path)?;
+        write_json_file(&path, &value)?;
+        references_updated += changed;
+        files.push(rel);
+    }
+
+    // Every reference has moved to its canonical copy by now.
+    let root = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let mut bytes_removed = 0;
+    for (duplicate, (_, len)) in &redirect {
+        if !duplicate.starts_with(&root) {
+            continue;
+        }
+        fs::remove_file(duplicate)
+            .map_err(|e| io_err(format!("delete {} failed: {e}", fmt_path(duplicate))))?;
+        bytes_removed += len;
+    }
+    let bytes_saved = bytes_removed.saturating_sub(bytes_written);
+    Ok(DedupeReport {
+        bytes_saved,
+        references_updated,
+        canonical_files,
+        files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn icon_bytes_on_disk(dir: &Path) -> u64 {
+        let mut total = 0;
+        for entry in fs::read_dir(dir).unwrap().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                total += icon_bytes_on_disk(&path);
+            } else if path.extension().is_some_and(|x| x == "png") {
+                total += entry.metadata().unwrap().len();
+            }
+        }
+        total
+    }
+
+    #[test]
+    fn dedupe_reports_what_it_removed_from_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = dir.path();
+        fs::create_dir_all(lib.join("sub")).unwrap();
+        let png = [7u8; 64];
+        fs::write(lib.join("cut.png"), png).unwrap();
+        fs::write(lib.join("sub/cut copy.png"), png).unwrap();
+        fs::write(lib.join("sub/fillet.png"), [1u8; 32]).unwrap();
+        let menu = |icon: &str| {
+            serde_json::json!({
+                "name": "Menu",
+                "command": "menu",
+                "items": [{"command": "cut", "icon": icon, "label": "Cut"}],
+            })
+        };
+        write_json_file(&lib.join("a.json"), &menu("cut.png")).unwrap();
+        let mut b = menu("cut copy.png");
+        b["items"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!({"command": "fillet", "icon": "fillet.png", "label": "F"}));
+        write_json_file(&lib.join("sub/b.json"), &b).unwrap();
+
+        let before = icon_bytes_on_disk(lib);
+        let icons = lib.join("icons");
+        let report = dedupe_icons(fmt_path(lib), fmt_path(&icons)).unwrap();
+        let after = icon_bytes_on_disk(lib);
+
+        assert_eq!(report.references_updated, 2);
+        assert_eq!(report.bytes_saved, before - after);
+        assert_eq!(report.bytes_saved, 64);
+        assert!(icons.join("cut.png").is_file());
+        assert!(!lib.join("cut.png").exists());
+        assert!(!lib.join("sub/cut copy.png").exists());
+        assert!(lib.join("sub/fillet.png").is_file());
+    }
+}
@@ -0,0 +1,84 @@
+use std::{fs, path::PathBuf};
+
+use tauri::ipc::{Invoke, InvokeBody};
+use tauri_plugin_opener::OpenerExt;
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+
+use crate::{app_data_dir, fmt_path, io_err};
+
+const LOG_PREFIX: &str = "radial-builder";
+const MAX_LOG_FILES: usize = 5;
+
+/// Keeps the background log writer alive; dropping it flushes and stops it.
+pub struct LogGuard(#[allow(dead_code)] WorkerGuard);
+
+fn logs_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_data_dir(app)?.join("logs"))
+}
+
+// ---------- Setup ----------
+/// Daily-rotated files in app_data_dir/logs, keeping the last few days.
+pub fn init(app: &tauri::AppHandle) -> Result<LogGuard, String> {
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_PREFIX)
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(logs_dir(app)?)
+        .map_err(|e| io_err(format!("create log file failed: {e}")))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    tracing_subscriber::fmt()
+        .with_writer(writer)
+        .with_ansi(false)
+        .try_init()
+        .map_err(|e| io_err(format!("init logging failed: {e}")))?;
+    Ok(LogGuard(guard))
+}
+
+/// Records which command ran and the path-like arguments it got. Menu
+/// contents are left out on purpose: they're large and may be private.
+pub fn log_invoke(invoke: &Invoke) {
+    let command = invoke.message.command();
+    let mut paths = vec![];
+    if let InvokeBody::Json(serde_json::Value::Object(args)) = invoke.message.payload() {
+        for (key, value) in args {
+            let lower = key.to_lowercase();
+            let is_path = lower.ends_with("path")
+                || lower.contains("directory")
+                || matches!(lower.as_str(), "from" | "to");
+            if let (true, Some(v)) = (is_path, value.as_str()) {
+                paths.push(format!("{key}={v}"));
+            }
+        }
+    }
+    tracing::info!(command, args = %paths.join(" "), "invoke");
+}
+
+// ---------- Commands ----------
+/// The file today's entries are going to. Rotated names end in the date
+/// (`radial-builder.2024-05-01.log`), so the newest sorts last.
+#[tauri::command]
+pub fn get_log_path(app: tauri::AppHandle) -> Result<String, String> {
+    let dir = logs_dir(&app)?;
+    let entries =
+        fs::read_dir(&dir).map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(&dir))))?;
+    let prefix = format!("{LOG_PREFIX}.");
+    entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with(&prefix) && name.ends_with(".log"))
+        .max()
+        .map(|name| fmt_path(&dir.join(name)))
+        .ok_or_else(|| io_err(format!("no log file in {} yet", fmt_path(&dir))))
+}
+
+#[tauri::command]
+pub fn open_log(app: tauri::AppHandle) -> Result<(), String> {
+    let dir = logs_dir(&app)?;
+    app.opener()
+        .open_path(fmt_path(&dir), None::<&str>)
+        .map_err(|e| io_err(format!("open {} failed: {e}", fmt_path(&dir))))
+}
@@ -1,13 +1,39 @@
 use std::{
-    fs, io,
+    fs,
+    io::{self, Write},
     path::{Path, PathBuf},
 };
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 use tauri_plugin_dialog::DialogExt;
 
+mod access;
+mod archive;
+mod autosave;
+mod backups;
+mod hotkeys;
+mod interop;
+mod keys;
+mod library;
+mod logging;
+mod menu;
+mod migrate;
+mod outline;
+mod palette;
+mod plasticity;
+mod prefs;
+mod render;
+mod submenus;
+mod volume;
+mod watch;
+mod workspace;
+
 // ---------- Error helpers ----------
+// Every command error is built here, which makes it the one place that
+// needs to log them.
 fn io_err<T: ToString>(msg: T) -> String {
-    msg.to_string()
+    let msg = msg.to_string();
+    tracing::warn!("{msg}");
+    msg
 }
 fn fmt_path(p: &Path) -> String {
     p.to_string_lossy().into_owned()
@@ -26,38 +52,157 @@ fn app_data_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
     Ok(base)
 }
 
+/// Holds the chosen radials directory, in app_data_dir.
+const RADIALS_DIR_MARKER: &str = "radials_dir.txt";
+
 fn radials_dir_marker_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
     let dir = app_data_dir(app)?;
-    Ok(dir.join("radials_dir.txt"))
+    Ok(dir.join(RADIALS_DIR_MARKER))
+}
+
+// ---------- File metadata helpers ----------
+/// Modified time in milliseconds since the Unix epoch (what JS `Date` uses),
+/// or None when the file doesn't exist.
+fn file_mtime_ms(path: &Path) -> Result<Option<i64>, String> {
+    let meta = match fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(io_err(format!("stat {} failed: {e}", fmt_path(path)))),
+    };
+    let modified = meta
+        .modified()
+        .map_err(|e| io_err(format!("mtime {} failed: {e}", fmt_path(path))))?;
+    let ms = match modified.duration_since(std::time::UNIX_EPOCH) {
+        Ok(d) => d.as_millis() as i64,
+        Err(e) => -(e.duration().as_millis() as i64),
+    };
+    Ok(Some(ms))
+}
+
+/// Gives `dest` the modified time of `src`, for exports whose consumers
+/// sort by mtime.
+fn copy_mtime(src: &Path, dest: &Path) -> Result<(), String> {
+    let meta =
+        fs::metadata(src).map_err(|e| io_err(format!("stat {} failed: {e}", fmt_path(src))))?;
+    let mtime = filetime::FileTime::from_last_modification_time(&meta);
+    filetime::set_file_mtime(dest, mtime)
+        .map_err(|e| io_err(format!("set mtime on {} failed: {e}", fmt_path(dest))))
 }
 
 // ---------- JSON file helpers ----------
 fn read_json_file(path: &Path) -> Result<serde_json::Value, String> {
-    let data = fs::read_to_string(path)
-        .map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(path))))?;
-    serde_json::from_str(&data)
-        .map_err(|e| io_err(format!("parse {} failed: {e}", fmt_path(path))))
+    let (value, warning) = read_json_file_lossy(path)?;
+    if let Some(warning) = warning {
+        tracing::warn!("{warning}");
+    }
+    Ok(value)
+}
+
+/// Like read_json_file, but also reports when the file wasn't valid UTF-8
+/// and had to be decoded lossily (invalid bytes become U+FFFD).
+fn read_json_file_lossy(path: &Path) -> Result<(serde_json::Value, Option<String>), String> {
+    let (data, warning) = read_text_lossy(path)?;
+    let value = serde_json::from_str(&data)
+        .map_err(|e| io_err(format!("parse {} failed: {e}", fmt_path(path))))?;
+    Ok((value, warning))
+}
+
+fn read_text_lossy(path: &Path) -> Result<(String, Option<String>), String> {
+    let bytes =
+        fs::read(path).map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(path))))?;
+    let (data, warning) = match String::from_utf8(bytes) {
+        Ok(data) => (data, None),
+        Err(e) => {
+            let warning = format!(
+                "{} is not valid UTF-8 ({}); invalid bytes were replaced",
+                fmt_path(path),
+                e.utf8_error()
+            );
+            (String::from_utf8_lossy(e.as_bytes()).into_owned(), Some(warning))
+        }
+    };
+    Ok((data, warning))
 }
 
+/// Writes through a synced temp file like write_file_atomic, keeping the
+/// file it replaces as `<name>.bak` so there's always one known-good
+/// previous version next to it.
 fn write_json_file(path: &Path, value: &serde_json::Value) -> Result<(), String> {
-    let pretty = serde_json::to_string_pretty(value)
-        .map_err(|e| io_err(format!("serialize json failed: {e}")))?;
+    let pretty = prefs::with_line_endings(prefs::to_json_string(value)?);
+    write_file_with_bak(path, pretty.as_bytes())
+}
+
+fn write_file_atomic(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    replace_file(path, bytes, false)
+}
+
+fn write_file_with_bak(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    replace_file(path, bytes, true)
+}
+
+fn replace_file(path: &Path, bytes: &[u8], keep_bak: bool) -> Result<(), String> {
     if let Some(parent) = path.parent() {
         if !parent.exists() {
             fs::create_dir_all(parent)
                 .map_err(|e| io_err(format!("create dir {} failed: {e}", fmt_path(parent))))?;
         }
     }
-    fs::write(path, pretty)
-        .map_err(|e| io_err(format!("write {} failed: {e}", fmt_path(path))))
+    // Write next to the target, flush it to disk and rename over it, so a
+    // crash or force-quit mid-save never leaves a truncated file behind. The
+    // temp file sits in the same directory to keep the rename on one
+    // filesystem, and is removed again on any failure.
+    let tmp = tmp_path(path);
+    let fail = |e: io::Error, what: String| io_err(format!("{what} failed: {e}"));
+    let result = write_synced(&tmp, bytes)
+        .map_err(|e| fail(e, format!("write {}", fmt_path(&tmp))))
+        .and_then(|()| {
+            if !keep_bak || !path.exists() {
+                return Ok(());
+            }
+            let bak = sibling_path(path, ".bak");
+            fs::copy(path, &bak)
+                .map(|_| ())
+                .map_err(|e| fail(e, format!("back up {}", fmt_path(path))))
+        })
+        .and_then(|()| {
+            fs::rename(&tmp, path).map_err(|e| fail(e, format!("replace {}", fmt_path(path))))
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+fn write_synced(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    sibling_path(path, ".tmp")
+}
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
 }
 
 // ---------- Commands ----------
 #[tauri::command]
 fn load_commands(app: tauri::AppHandle) -> Result<serde_json::Value, String> {
-    // Just use embedded fallback for now
-    let data = include_str!("../../dist/commands.json");
-    serde_json::from_str(data).map_err(|e| format!("embedded commands.json parse failed: {e}"))
+    // The frontend only needs id -> icon; richer fields stay on the backend.
+    let icons = palette::merged_commands(&app)?
+        .into_iter()
+        .map(|(id, entry)| {
+            let icon = palette::entry_icon(&entry)
+                .map(|i| serde_json::Value::String(i.to_string()))
+                .unwrap_or(serde_json::Value::Null);
+            (id, icon)
+        })
+        .collect();
+    Ok(serde_json::Value::Object(icons))
 }
 
 #[tauri::command]
@@ -65,49 +210,247 @@ fn load_commands_from_file(path: String) -> Result<serde_json::Value, String> {
     read_json_file(Path::new(&path))
 }
 
+#[derive(serde::Serialize)]
+struct CheckedJson {
+    value: serde_json::Value,
+    warning: Option<String>,
+}
+
+#[tauri::command]
+fn load_commands_from_file_checked(path: String) -> Result<CheckedJson, String> {
+    let (value, warning) = read_json_file_lossy(Path::new(&path))?;
+    Ok(CheckedJson { value, warning })
+}
+
 #[tauri::command]
-fn list_json_files(directory: String) -> Result<Vec<String>, String> {
-    let dir = PathBuf::from(&directory);
-    if !dir.exists() {
-        return Err(io_err(format!("directory {} does not exist", directory)));
+fn list_json_files(
+    directory: String,
+    locale: Option<String>,
+    absolute: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<Vec<String>, String> {
+    access::with_file_access(&app, || {
+        let dir = Path::new(&directory);
+        let mut files = library::menu_file_names(dir)?;
+        if let Some(locale) = locale {
+            library::collate(&mut files, &locale);
+        }
+        if absolute.unwrap_or(false) {
+            let root = fs::canonicalize(dir)
+                .map_err(|e| io_err(format!("resolve {directory} failed: {e}")))?;
+            files = files.iter().map(|name| fmt_path(&root.join(name))).collect();
+        }
+        Ok(files)
+    })
+}
+
+/// Every menu under `directory`, subfolders included, as forward-slash
+/// paths relative to it (`modeling/cut.json`). list_json_files stays flat.
+#[tauri::command]
+fn list_json_files_recursive(directory: String) -> Result<Vec<String>, String> {
+    library::menu_paths(Path::new(&directory), true)
+}
+
+#[tauri::command]
+fn load_radial_menu(path: String, app: tauri::AppHandle) -> Result<serde_json::Value, String> {
+    access::with_file_access(&app, || read_menu(Path::new(&path)))
+}
+
+fn read_menu(path: &Path) -> Result<serde_json::Value, String> {
+    // Older files are upgraded in memory; they're only rewritten on save.
+    migrate::migrate_menu(read_json_file(path)?)
+}
+
+#[derive(serde::Serialize)]
+struct RawMenu {
+    value: serde_json::Value,
+    text: String,
+}
+
+/// The parsed menu together with the file's text exactly as stored, for the
+/// source view. Unlike load_radial_menu nothing is migrated.
+#[tauri::command]
+fn load_radial_menu_raw(path: String) -> Result<RawMenu, String> {
+    let path = Path::new(&path);
+    let (text, warning) = read_text_lossy(path)?;
+    if let Some(warning) = warning {
+        tracing::warn!("{warning}");
     }
+    let value = serde_json::from_str(&text)
+        .map_err(|e| io_err(format!("parse {} failed: {e}", fmt_path(path))))?;
+    Ok(RawMenu { value, text })
+}
+
+#[derive(serde::Serialize)]
+struct MenuFocus {
+    menu: serde_json::Value,
+    slice_index: usize,
+}
+
+/// load_radial_menu for jumping straight to a slot, e.g. from a search hit.
+/// An index past the end of the ring is an error rather than clamped, since
+/// it means the file changed since the hit was found.
+#[tauri::command]
+fn load_radial_menu_focus(
+    path: String,
+    slice_index: usize,
+    app: tauri::AppHandle,
+) -> Result<MenuFocus, String> {
+    let menu = load_radial_menu(path.clone(), app)?;
+    let slots = menu::items(&menu)?.len();
+    if slice_index >= slots {
+        return Err(io_err(format!(
+            "slot {slice_index} is out of range ({path} has {slots} slots)"
+        )));
+    }
+    Ok(MenuFocus { menu, slice_index })
+}
 
-    let mut files = vec![];
-    for entry in fs::read_dir(&dir)
-        .map_err(|e| io_err(format!("read_dir {} failed: {e}", directory)))?
-    {
-        let entry = entry.map_err(|e| io_err(format!("dir entry error: {e}")))?;
-        let path = entry.path();
-        if path.extension().map(|x| x == "json").unwrap_or(false) {
-            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                files.push(name.to_string());
+/// Saves the menu, first tidying labels: trimmed unless `trim_labels` is
+/// false, inner whitespace collapsed with `collapse_spaces`. With
+/// `embed_hash` the file also records its own content hash for
+/// verify_menu_integrity. A new file that takes its directory past the
+/// preferences' menu_count_limit is still saved, with a warning. Returns how
+/// many labels were cleaned.
+#[tauri::command]
+fn save_radial_menu(
+    mut menu: serde_json::Value,
+    path: String,
+    base_mtime: Option<i64>,
+    trim_labels: Option<bool>,
+    collapse_spaces: Option<bool>,
+    embed_hash: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<usize, String> {
+    access::with_file_access(&app, || {
+        let path = Path::new(&path);
+        // base_mtime is what the frontend saw when it loaded the file; anything
+        // newer on disk means someone else wrote it in the meantime.
+        if let (Some(base), Some(current)) = (base_mtime, file_mtime_ms(path)?) {
+            if current > base {
+                return Err(io_err(format!(
+                    "file changed on disk: {} was modified after it was loaded",
+                    fmt_path(path)
+                )));
             }
         }
-    }
-    files.sort();
-    Ok(files)
+        let cleaned = if trim_labels.unwrap_or(true) {
+            menu::tidy_labels(&mut menu, collapse_spaces.unwrap_or(false))
+        } else {
+            0
+        };
+        if embed_hash.unwrap_or(false) {
+            menu::embed_content_hash(&mut menu);
+        }
+        // Checked last, on exactly what would be written; a bad menu leaves the
+        // file untouched.
+        menu::validate_menu(&menu).map_err(menu::validation_error)?;
+        let is_new = !path.exists();
+        backups::backup_existing(path)?;
+        write_json_file(path, &menu)?;
+        autosave::clear(path)?;
+        let limit = prefs::active().menu_count_limit.filter(|_| is_new);
+        if let (Some(max), Some(dir)) = (limit, path.parent()) {
+            let status = library::menu_count_status(dir, max)?;
+            if !status.within {
+                tracing::warn!(
+                    "{} now holds {} menus, over the limit of {max}",
+                    fmt_path(dir),
+                    status.count
+                );
+                if let Err(e) = app.emit("menu-count-limit", status) {
+                    tracing::warn!("emit menu-count-limit failed: {e}");
+                }
+            }
+        }
+        Ok(cleaned)
+    })
 }
 
+/// Saves source-view text as typed, keeping its formatting, but only once it
+/// parses and passes validate_radial_menu.
 #[tauri::command]
-fn load_radial_menu(path: String) -> Result<serde_json::Value, String> {
-    read_json_file(Path::new(&path))
+fn save_radial_menu_raw(text: String, path: String, app: tauri::AppHandle) -> Result<(), String> {
+    // serde_json's message already carries the line and column.
+    let menu: serde_json::Value =
+        serde_json::from_str(&text).map_err(|e| io_err(format!("invalid JSON: {e}")))?;
+    menu::validate_radial_menu(menu)?;
+    access::with_file_access(&app, || {
+        let path = Path::new(&path);
+        backups::backup_existing(path)?;
+        write_file_atomic(path, text.as_bytes())
+    })
+}
+
+fn is_json_path(path: &Path) -> bool {
+    path.extension().map(|x| x == "json").unwrap_or(false)
 }
 
+/// Deletes a menu file, keeping a copy in its backups first so the delete
+/// can be undone from there. Its autosave goes with it.
 #[tauri::command]
-fn save_radial_menu(menu: serde_json::Value, path: String) -> Result<(), String> {
-    write_json_file(Path::new(&path), &menu)
+fn delete_radial_menu(path: String, app: tauri::AppHandle) -> Result<(), String> {
+    access::with_file_access(&app, || {
+        let path = Path::new(&path);
+        if !is_json_path(path) {
+            return Err(io_err(format!("{} is not a .json menu", fmt_path(path))));
+        }
+        if !path.is_file() {
+            return Err(io_err(format!("{} no longer exists", fmt_path(path))));
+        }
+        backups::backup_existing(path)?;
+        fs::remove_file(path)
+            .map_err(|e| io_err(format!("delete {} failed: {e}", fmt_path(path))))?;
+        autosave::clear(path)
+    })
 }
 
+/// Moves a menu file to `to`, creating its folder if needed. An existing
+/// `to` is never overwritten.
 #[tauri::command]
-fn save_radials_directory(path: String, app: tauri::AppHandle) -> Result<(), String> {
-    let marker = radials_dir_marker_path(&app)?;
-    fs::write(&marker, &path)
-        .map_err(|e| io_err(format!("persist dir to {} failed: {e}", fmt_path(&marker))))
+fn rename_radial_menu(from: String, to: String, app: tauri::AppHandle) -> Result<(), String> {
+    access::with_file_access(&app, || {
+        let (from, to) = (Path::new(&from), Path::new(&to));
+        if !is_json_path(from) || !is_json_path(to) {
+            return Err(io_err("menus can only be renamed from and to .json files"));
+        }
+        if !from.is_file() {
+            return Err(io_err(format!("{} no longer exists", fmt_path(from))));
+        }
+        if to.exists() {
+            return Err(io_err(format!("{} already exists", fmt_path(to))));
+        }
+        if let Some(parent) = to.parent() {
+            if !parent.exists() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| io_err(format!("create dir {} failed: {e}", fmt_path(parent))))?;
+            }
+        }
+        fs::rename(from, to).map_err(|e| {
+            io_err(format!(
+                "rename {} to {} failed: {e}",
+                fmt_path(from),
+                fmt_path(to)
+            ))
+        })?;
+        autosave::clear(from)
+    })
+}
+
+#[tauri::command]
+fn get_file_mtime(path: String) -> Result<Option<i64>, String> {
+    file_mtime_ms(Path::new(&path))
 }
 
 #[tauri::command]
-fn get_saved_radials_directory(app: tauri::AppHandle) -> Result<Option<String>, String> {
+fn save_radials_directory(path: String, app: tauri::AppHandle) -> Result<(), String> {
     let marker = radials_dir_marker_path(&app)?;
+    write_file_with_bak(&marker, path.as_bytes())
+}
+
+/// The radials directory marker's contents, if one has been saved.
+fn saved_radials_directory(app: &tauri::AppHandle) -> Result<Option<String>, String> {
+    let marker = radials_dir_marker_path(app)?;
     match fs::read_to_string(&marker) {
         Ok(s) => Ok(Some(s.trim().to_string())),
         Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
@@ -115,6 +458,117 @@ fn get_saved_radials_directory(app: tauri::AppHandle) -> Result<Option<String>,
     }
 }
 
+#[derive(serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum SavedDirectory {
+    Available {
+        path: String,
+    },
+    /// Gone from a volume that is still there; worth picking again.
+    Missing {
+        path: String,
+    },
+    /// On a removable or network volume that isn't mounted right now, so
+    /// it will likely be back once the drive is plugged in.
+    Disconnected {
+        path: String,
+        volume: String,
+    },
+}
+
+#[tauri::command]
+fn get_saved_radials_directory(app: tauri::AppHandle) -> Result<Option<SavedDirectory>, String> {
+    let Some(path) = saved_radials_directory(&app)? else {
+        return Ok(None);
+    };
+    let dir = Path::new(&path);
+    Ok(Some(if dir.is_dir() {
+        SavedDirectory::Available { path }
+    } else if let Some(volume) = volume::unmounted_volume(dir) {
+        SavedDirectory::Disconnected {
+            volume: fmt_path(&volume),
+            path,
+        }
+    } else {
+        SavedDirectory::Missing { path }
+    }))
+}
+
+// A path that doesn't exist yet (a save target, say) resolves through its
+// parent so it can still be compared.
+fn canonicalize_lenient(path: &Path) -> Option<PathBuf> {
+    if let Ok(real) = fs::canonicalize(path) {
+        return Some(real);
+    }
+    let parent = fs::canonicalize(path.parent()?).ok()?;
+    Some(parent.join(path.file_name()?))
+}
+
+/// Whether `path` lives inside the saved radials directory, symlinks and
+/// `..` resolved. False when no directory is saved or it no longer exists.
+#[tauri::command]
+fn is_within_radials_directory(path: String, app: tauri::AppHandle) -> Result<bool, String> {
+    let Some(dir) = saved_radials_directory(&app)? else {
+        return Ok(false);
+    };
+    let (Ok(dir), Some(path)) = (fs::canonicalize(&dir), canonicalize_lenient(Path::new(&path)))
+    else {
+        return Ok(false);
+    };
+    Ok(path.starts_with(&dir))
+}
+
+// ---------- Startup ----------
+#[derive(Clone, serde::Serialize)]
+struct StartupInfo {
+    radials_directory: Option<String>,
+    commands_source: String,
+    version: String,
+}
+
+/// Sent as the `ready` event from setup. Also a command, since a webview that
+/// attaches its listener late would otherwise miss the event.
+#[tauri::command]
+fn startup_info(app: tauri::AppHandle) -> Result<StartupInfo, String> {
+    Ok(StartupInfo {
+        commands_source: palette::commands_source(&app)?,
+        version: app.package_info().version.to_string(),
+        radials_directory: saved_radials_directory(&app)?,
+    })
+}
+
+// ---------- Window ----------
+/// The label Tauri gives the window defined in tauri.conf.json.
+const MAIN_WINDOW: &str = "main";
+
+/// Retitles the main window, e.g. `Modeling.json *` while there are unsaved
+/// changes. A missing window (mid-shutdown, say) is logged, not an error.
+#[tauri::command]
+fn set_window_title(title: String, app: tauri::AppHandle) -> Result<(), String> {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        tracing::warn!("no {MAIN_WINDOW} window to set the title on");
+        return Ok(());
+    };
+    window
+        .set_title(&title)
+        .map_err(|e| io_err(format!("set window title failed: {e}")))
+}
+
+/// A second launch (a share link opened while the app is running, say) is
+/// handed to this instance instead of opening another window. The
+/// deep-link feature has already passed any radialbuilder:// URL in `args`
+/// to on_open_url, which emits the import; this brings the window forward
+/// so it's seen.
+fn on_second_instance(app: &tauri::AppHandle, args: Vec<String>, _cwd: String) {
+    tracing::info!("second launch forwarded {} args", args.len());
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return;
+    };
+    if let Err(e) = window.unminimize().and_then(|()| window.set_focus()) {
+        tracing::warn!("focus {MAIN_WINDOW} window failed: {e}");
+    }
+}
+
 // ---------- Simple dialog commands ----------
 #[tauri::command]
 fn pick_directory(app: tauri::AppHandle) -> Result<Option<String>, String> {
@@ -133,34 +587,287 @@ fn pick_json_file(app: tauri::AppHandle) -> Result<Option<String>, String> {
     Ok(picked.map(|p| p.to_string()))
 }
 
+#[derive(serde::Serialize)]
+struct SavePick {
+    /// The name the dialog was actually seeded with, after sanitizing.
+    suggested_name: Option<String>,
+    /// None when the dialog was cancelled.
+    path: Option<String>,
+}
+
 #[tauri::command]
-fn pick_save_json_path(app: tauri::AppHandle, suggested_name: Option<String>) -> Result<Option<String>, String> {
+fn pick_save_json_path(app: tauri::AppHandle, suggested_name: Option<String>) -> Result<SavePick, String> {
+    let suggested_name = suggested_name.map(library::sanitize_menu_filename);
     let mut builder = app.dialog().file().add_filter("JSON", &["json"]);
-    if let Some(name) = suggested_name {
-        builder = builder.set_file_name(&name);
+    if let Some(name) = &suggested_name {
+        builder = builder.set_file_name(name);
     }
     let picked = builder
         .set_title("Save radial menu as…")
         .blocking_save_file();
-    Ok(picked.map(|p| p.to_string()))
+    Ok(SavePick {
+        suggested_name,
+        path: picked.map(|p| p.to_string()),
+    })
 }
 
 fn main() {
+    let handler: fn(tauri::ipc::Invoke) -> bool = tauri::generate_handler![
+        load_commands,
+        load_commands_from_file,
+        load_commands_from_file_checked,
+        list_json_files,
+        list_json_files_recursive,
+        load_radial_menu,
+        load_radial_menu_raw,
+        load_radial_menu_focus,
+        outline::outline_menu_json,
+        save_radial_menu,
+        save_radial_menu_raw,
+        delete_radial_menu,
+        rename_radial_menu,
+        autosave::autosave_menu,
+        autosave::get_autosave,
+        autosave::discard_autosave,
+        autosave::diff_autosave,
+        get_file_mtime,
+        save_radials_directory,
+        get_saved_radials_directory,
+        access::request_file_access,
+        is_within_radials_directory,
+        workspace::save_workspace,
+        workspace::load_workspace,
+        startup_info,
+        set_window_title,
+        pick_directory,
+        pick_json_file,
+        pick_save_json_path,
+        menu::validate_radial_menu,
+        menu::validate_against_schema,
+        menu::verify_menu_integrity,
+        menu::normalize_color,
+        menu::is_radial_menu_file,
+        menu::apply_json_patch,
+        menu::export_menu_patch,
+        menu::autofill_slice_labels,
+        menu::find_overlong_labels,
+        menu::find_absolute_icon_paths,
+        menu::relativize_icon_paths,
+        menu::validate_icon_formats,
+        menu::duplicate_slice,
+        menu::swap_slices,
+        menu::rotate_slices,
+        menu::reindex_slices,
+        menu::extract_tagged_slices,
+        menu::split_menu,
+        menu::export_slice_snippet,
+        menu::import_slice_snippet,
+        menu::menu_stats,
+        render::export_menu_preview_svg,
+        render::export_menu_preview_png,
+        render::export_menu_pdf,
+        render::export_menu_html,
+        render::estimate_layout,
+        palette::ensure_user_commands,
+        palette::load_commands_grouped,
+        palette::reset_commands_to_default,
+        palette::validate_commands_overlay,
+        palette::check_embedded_commands,
+        palette::export_commands_csv,
+        palette::import_commands_csv,
+        palette::diff_commands,
+        palette::check_menu_against_commands,
+        keys::resolve_effective_keybindings,
+        keys::find_keybinding_conflicts,
+        keys::auto_assign_keys,
+        keys::find_override_drift,
+        keys::find_menus_using_key,
+        keys::export_keybinding_cheatsheet,
+        plasticity::export_to_plasticity,
+        plasticity::export_all_to_plasticity,
+        plasticity::import_from_plasticity,
+        plasticity::verify_plasticity_roundtrip,
+        plasticity::find_plasticity_config_dir,
+        plasticity::default_radials_directory,
+        backups::menu_history,
+        backups::list_menu_history,
+        backups::restore_menu_version,
+        backups::compact_backups,
+        backups::find_orphaned_backups,
+        backups::clean_orphaned_backups,
+        library::load_radial_menu_by_index,
+        library::directory_fingerprint,
+        library::verify_directory,
+        library::lint_directory_json,
+        library::group_menus_by_missing_commands,
+        library::command_usage_stats,
+        library::minimal_commands_for_directory,
+        library::suggest_starter_menu,
+        library::build_menu_from_command_list,
+        library::generate_test_menu,
+        library::find_label_inconsistencies,
+        library::find_empty_menus,
+        library::ring_size_distribution,
+        library::check_menu_count_limit,
+        library::find_stale_menus,
+        submenus::find_submenu_cycles,
+        submenus::resolve_submenu_tree,
+        library::benchmark_load,
+        library::profile_directory_loads,
+        library::load_directory_notes,
+        library::save_directory_notes,
+        library::list_example_menus,
+        library::load_example_menu,
+        library::sanitize_menu_filename,
+        library::find_unsafe_filenames,
+        library::check_naming_policy,
+        library::find_title_filename_mismatches,
+        library::sync_title_to_filename,
+        library::suggest_menu_name,
+        library::replace_command_everywhere,
+        library::command_impact,
+        library::find_deprecated_commands,
+        library::dedupe_icons,
+        library::rename_command,
+        migrate::migrate_directory,
+        menu::copy_menu_to_clipboard,
+        menu::paste_menu_from_clipboard,
+        prefs::get_preferences,
+        prefs::set_preferences,
+        watch::set_inbox_directory,
+        watch::watch_radials_directory,
+        watch::stop_watching_radials_directory,
+        hotkeys::register_menu_hotkey,
+        hotkeys::unregister_menu_hotkey,
+        interop::export_library_combined,
+        interop::import_library_combined,
+        interop::explode_combined_library,
+        interop::export_menus_by_tag,
+        interop::import_menus_from_directory,
+        interop::export_radial_menu_toml,
+        interop::import_radial_menu_toml,
+        interop::import_radial_menu_yaml,
+        interop::convert_directory_yaml_to_json,
+        interop::snapshot_menu_with_metadata,
+        interop::export_release,
+        interop::export_labels_for_translation,
+        interop::apply_translated_labels,
+        interop::menu_to_share_link,
+        interop::menu_from_share_link,
+        archive::export_full_backup,
+        archive::import_full_backup,
+        archive::export_radials_bundle,
+        archive::import_radials_bundle,
+        archive::export_app_state,
+        logging::get_log_path,
+        logging::open_log
+    ];
+
     tauri::Builder::default()
+        // Registered first so a second launch exits before anything else
+        // starts.
+        .plugin(tauri_plugin_single_instance::init(on_second_instance))
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
-        .invoke_handler(tauri::generate_handler![
-            load_commands,
-            load_commands_from_file,
-            list_json_files,
-            load_radial_menu,
-            save_radial_menu,
-            save_radials_directory,
-            get_saved_radials_directory,
-            pick_directory,
-            pick_json_file,
-            pick_save_json_path
-        ])
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_deep_link::init())
+        .manage(watch::Watchers::default())
+        .manage(access::FileAccessState::default())
+        .setup(|app| {
+            match logging::init(app.handle()) {
+                Ok(guard) => {
+                    app.manage(guard);
+                }
+                Err(e) => eprintln!("{e}"),
+            }
+            // The rest only degrades features if it fails; don't block startup.
+            let _ = prefs::load(app.handle());
+            hotkeys::restore(app.handle());
+            interop::listen_for_share_links(app.handle());
+            let _ = watch::watch_commands_overlay(app.handle());
+            let _ = watch::watch_inbox(app.handle());
+            if let Ok(info) = startup_info(app.handle().clone()) {
+                if let Err(e) = app.emit("ready", info) {
+                    tracing::warn!("emit ready failed: {e}");
+                }
+            }
+            Ok(())
+        })
+        .invoke_handler(move |invoke| {
+            logging::log_invoke(&invoke);
+            handler(invoke)
+        })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_temp_write_leaves_the_original_and_its_bak() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("menu.json");
+        fs::write(&path, b"original").unwrap();
+        fs::write(sibling_path(&path, ".bak"), b"previous").unwrap();
+        // The temp file can't be created where a directory already is.
+        fs::create_dir(tmp_path(&path)).unwrap();
+
+        assert!(write_file_with_bak(&path, b"replacement").is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"original");
+        assert_eq!(fs::read(sibling_path(&path, ".bak")).unwrap(), b"previous");
+        assert_eq!(fs::read_dir(tmp_path(&path)).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn failed_bak_copy_removes_the_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("menu.json");
+        fs::write(&path, b"original").unwrap();
+        // The temp file is written, then copying the old file to `.bak` fails.
+        fs::create_dir(sibling_path(&path, ".bak")).unwrap();
+
+        assert!(write_file_with_bak(&path, b"replacement").is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"original");
+        assert!(sibling_path(&path, ".bak").is_dir());
+        assert!(!tmp_path(&path).exists());
+    }
+
+    #[test]
+    fn latin1_labels_are_decoded_lossily_with_a_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("menu.json");
+        // "Café" as Latin-1: a lone 0xE9 where UTF-8 needs two bytes.
+        let mut bytes = br#"{"name":"Test","command":"default-menu:test","items":["#.to_vec();
+        bytes.extend_from_slice(br#"{"command":"command:test","icon":"","label":"Caf"#);
+        bytes.push(0xE9);
+        bytes.extend_from_slice(br#""}]}"#);
+        fs::write(&path, &bytes).unwrap();
+
+        let menu = read_json_file(&path).unwrap();
+        assert_eq!(menu["items"][0]["label"], "Caf\u{FFFD}");
+        let (lossy, warning) = read_json_file_lossy(&path).unwrap();
+        assert_eq!(lossy, menu);
+        let warning = warning.unwrap();
+        assert!(warning.contains("is not valid UTF-8"), "{warning}");
+    }
+
+    #[test]
+    fn saving_keeps_the_key_order_it_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("menu.json");
+        let original = r#"{"name":"Test","items":[{"label":"Move","icon":"","command":"command:move"}],"command":"default-menu:test","zeta":1,"alpha":2}"#;
+        fs::write(&path, original).unwrap();
+
+        let menu = read_json_file(&path).unwrap();
+        write_json_file(&path, &menu).unwrap();
+        let saved = read_json_file(&path).unwrap();
+
+        let keys =
+            |v: &serde_json::Value| v.as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+        assert_eq!(keys(&saved), ["name", "items", "command", "zeta", "alpha"]);
+        assert_eq!(keys(&saved["items"][0]), ["label", "icon", "command"]);
+    }
+}
@@ -0,0 +1,947 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    file_mtime_ms, fmt_path, io_err, keys, palette, prefs, read_json_file, write_json_file,
+};
+
+/// The frontend's socket spinner runs from 3 to 12 slots.
+pub const MIN_SLOTS: usize = 3;
+pub const MAX_SLOTS: usize = 12;
+
+// ---------- Item access ----------
+pub fn items(menu: &Value) -> Result<&Vec<Value>, String> {
+    menu.get("items")
+        .and_then(Value::as_array)
+        .ok_or_else(|| io_err("menu has no items array"))
+}
+
+pub fn items_mut(menu: &mut Value) -> Result<&mut Vec<Value>, String> {
+    menu.get_mut("items")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| io_err("menu has no items array"))
+}
+
+pub fn is_empty_slot(item: &Value) -> bool {
+    item_str(item, "command").is_empty()
+}
+
+fn check_index(items: &[Value], index: usize) -> Result<(), String> {
+    if index >= items.len() {
+        return Err(io_err(format!(
+            "slot {index} is out of range (menu has {} slots)",
+            items.len()
+        )));
+    }
+    Ok(())
+}
+
+pub fn empty_slot() -> Value {
+    serde_json::json!({ "command": "", "icon": "", "label": "" })
+}
+
+pub fn item_str<'a>(item: &'a Value, field: &str) -> &'a str {
+    item.get(field).and_then(Value::as_str).unwrap_or("")
+}
+
+/// Points every slot using `old_id` at `new_id`; returns how many changed.
+pub fn replace_command(menu: &mut Value, old_id: &str, new_id: &str) -> usize {
+    let Ok(items) = items_mut(menu) else {
+        return 0;
+    };
+    let mut changed = 0;
+    for item in items {
+        if item_str(item, "command") == old_id {
+            item["command"] = Value::String(new_id.to_string());
+            changed += 1;
+        }
+    }
+    changed
+}
+
+// ---------- Validation ----------
+/// The slot fields validation checks, deserialized. Fields the app doesn't
+/// know about are allowed and kept. An empty `command` is an empty slot.
+#[derive(Deserialize)]
+struct Slot {
+    #[serde(default)]
+    command: String,
+    #[serde(default)]
+    label: String,
+    index: Option<u64>,
+    color: Option<String>,
+}
+
+/// Structural checks for a builder menu. Every problem is collected so the
+/// caller can show them all at once instead of fixing one error at a time.
+/// Each part is read on its own so one bad slot doesn't hide the problems in
+/// the others; serde's type errors are reported under the field or slot they
+/// came from.
+pub fn validate_menu(value: &Value) -> Result<(), Vec<String>> {
+    let mut errors = vec![];
+    let Some(obj) = value.as_object() else {
+        return Err(vec!["menu must be a JSON object".into()]);
+    };
+
+    typed_field::<String>(obj, "name", &mut errors);
+    typed_field::<String>(obj, "command", &mut errors);
+    let items = typed_field::<Vec<Value>>(obj, "items", &mut errors).unwrap_or_default();
+    if items.len() > MAX_SLOTS {
+        errors.push(format!(
+            "menu has {} slots, the maximum is {MAX_SLOTS}",
+            items.len()
+        ));
+    }
+    for (i, item) in items.iter().enumerate() {
+        validate_item(i, item, items.len(), &mut errors);
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn typed_field<T: DeserializeOwned>(
+    obj: &Map<String, Value>,
+    field: &str,
+    errors: &mut Vec<String>,
+) -> Option<T> {
+    let Some(value) = obj.get(field) else {
+        errors.push(format!("missing {field}"));
+        return None;
+    };
+    T::deserialize(value)
+        .map_err(|e| errors.push(format!("{field}: {e}")))
+        .ok()
+}
+
+// serde's "invalid type" message doesn't say which field it was; find the
+// first one that doesn't deserialize on its own and put its name in front.
+fn slot_type_error(item: &Value, err: serde_json::Error) -> String {
+    let strings = ["command", "label", "color"];
+    for field in strings {
+        if let Some(Err(e)) = item.get(field).map(String::deserialize) {
+            return format!("{field}: {e}");
+        }
+    }
+    if let Some(Err(e)) = item.get(INDEX_FIELD).map(u64::deserialize) {
+        return format!("{INDEX_FIELD}: {e}");
+    }
+    err.to_string()
+}
+
+// `namespace:name`, possibly with more `:` parts, none of them empty and no
+// whitespace anywhere: `command:move`, `view:radial:default-menu:modeling`.
+fn is_valid_command_id(id: &str) -> bool {
+    id.contains(':')
+        && id
+            .split(':')
+            .all(|part| !part.is_empty() && !part.contains(char::is_whitespace))
+}
+
+// Adds the slot's problems to `errors`.
+fn validate_item(i: usize, item: &Value, slots: usize, errors: &mut Vec<String>) {
+    if !item.is_object() {
+        errors.push(format!("slot {i}: must be an object"));
+        return;
+    }
+    match Slot::deserialize(item) {
+        Ok(slot) => validate_slot(i, &slot, slots, errors),
+        Err(e) => errors.push(format!("slot {i}: {}", slot_type_error(item, e))),
+    }
+    // Only its type matters; validation never reads the icon itself.
+    if let Some(Err(e)) = item.get("icon").map(String::deserialize) {
+        errors.push(format!("slot {i}: icon: {e}"));
+    }
+}
+
+fn validate_slot(i: usize, slot: &Slot, slots: usize, errors: &mut Vec<String>) {
+    if !slot.command.is_empty() && slot.label.trim().is_empty() {
+        errors.push(format!("slot {i}: missing label"));
+    }
+    if !slot.command.is_empty() && !is_valid_command_id(&slot.command) {
+        errors.push(format!("slot {i}: invalid command id {:?}", slot.command));
+    }
+    if let Some(index) = slot.index {
+        if index as usize >= slots {
+            errors.push(format!(
+                "slot {i}: index {index} is out of range (menu has {slots} slots)"
+            ));
+        }
+    }
+    if let Some(color) = &slot.color {
+        if !is_full_hex_color(color) {
+            errors.push(format!(
+                "slot {i}: color {color:?} is not #RRGGBB or #RRGGBBAA"
+            ));
+        }
+    }
+}
+
+fn is_full_hex_color(c: &str) -> bool {
+    c.strip_prefix('#')
+        .map(|hex| matches!(hex.len(), 6 | 8) && hex.chars().all(|ch| ch.is_ascii_hexdigit()))
+        .unwrap_or(false)
+}
+
+/// Canonical lowercase `#rrggbb`/`#rrggbbaa`, expanding `#rgb` shorthand.
+#[tauri::command]
+pub fn normalize_color(value: String) -> Result<String, String> {
+    let trimmed = value.trim();
+    let hex = trimmed
+        .strip_prefix('#')
+        .filter(|h| h.chars().all(|ch| ch.is_ascii_hexdigit()))
+        .ok_or_else(|| io_err(format!("{value} is not a hex color")))?;
+    let full = match hex.len() {
+        3 => hex.chars().flat_map(|ch| [ch, ch]).collect(),
+        6 | 8 => hex.to_string(),
+        _ => return Err(io_err(format!("{value} is not a hex color"))),
+    };
+    Ok(format!("#{}", full.to_ascii_lowercase()))
+}
+
+pub fn validation_error(errors: Vec<String>) -> String {
+    io_err(format!("invalid radial menu: {}", errors.join("; ")))
+}
+
+#[tauri::command]
+pub fn validate_radial_menu(menu: Value) -> Result<(), String> {
+    validate_menu(&menu).map_err(validation_error)
+}
+
+/// Whether a JSON file is shaped like one of our menus. Only IO and parse
+/// failures are errors; any other JSON is just `false`.
+#[tauri::command]
+pub fn is_radial_menu_file(path: String) -> Result<bool, String> {
+    let value = read_json_file(Path::new(&path))?;
+    Ok(validate_menu(&value).is_ok())
+}
+
+// ---------- Custom schema ----------
+// The last compiled schema, keyed by path and mtime, so validating a batch
+// of menus against one schema compiles it once.
+struct CachedSchema {
+    path: PathBuf,
+    mtime: Option<i64>,
+    validator: Arc<jsonschema::Validator>,
+}
+
+static SCHEMA_CACHE: Mutex<Option<CachedSchema>> = Mutex::new(None);
+
+fn compiled_schema(path: &Path) -> Result<Arc<jsonschema::Validator>, String> {
+    let mtime = file_mtime_ms(path)?;
+    let mut cache = SCHEMA_CACHE
+        .lock()
+        .map_err(|_| io_err("schema cache is poisoned"))?;
+    if let Some(cached) = cache.as_ref() {
+        if cached.path == path && cached.mtime == mtime {
+            return Ok(cached.validator.clone());
+        }
+    }
+    let schema = read_json_file(path)?;
+    let validator = jsonschema::validator_for(&schema).map_err(|e| {
+        io_err(format!(
+            "{} is not a valid JSON Schema: {e}",
+            fmt_path(path)
+        ))
+    })?;
+    let validator = Arc::new(validator);
+    *cache = Some(CachedSchema {
+        path: path.to_path_buf(),
+        mtime,
+        validator: validator.clone(),
+    });
+    Ok(validator)
+}
+
+/// Checks the menu against a team's own JSON Schema, on top of the built-in
+/// structure. Returns every violation as `<json pointer>: <message>`; an
+/// empty list means it passes. Remote `$ref`s aren't fetched.
+#[tauri::command]
+pub fn validate_against_schema(menu: Value, schema_path: String) -> Result<Vec<String>, String> {
+    let validator = compiled_schema(Path::new(&schema_path))?;
+    let messages = validator
+        .iter_errors(&menu)
+        .map(|e| {
+            let at = e.instance_path.to_string();
+            format!("{}: {e}", if at.is_empty() { "/" } else { &at })
+        })
+        .collect();
+    Ok(messages)
+}
+
+// ---------- Integrity ----------
+/// Top-level field holding a menu's own content hash.
+pub const CONTENT_HASH_FIELD: &str = "_content_hash";
+
+// Objects with their keys sorted, so the hash doesn't depend on field order
+// or formatting.
+fn canonical(value: &Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let mut keys: Vec<&String> = fields.keys().collect();
+            keys.sort();
+            Value::Object(
+                keys.into_iter()
+                    .map(|k| (k.clone(), canonical(&fields[k])))
+                    .collect::<Map<String, Value>>(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonical).collect()),
+        _ => value.clone(),
+    }
+}
+
+/// SHA-256, as hex, of the menu's canonical compact JSON with
+/// CONTENT_HASH_FIELD left out.
+pub fn content_hash(menu: &Value) -> String {
+    let mut content = canonical(menu);
+    if let Value::Object(fields) = &mut content {
+        fields.shift_remove(CONTENT_HASH_FIELD);
+    }
+    format!("{:x}", Sha256::digest(content.to_string()))
+}
+
+pub fn embed_content_hash(menu: &mut Value) {
+    let hash = content_hash(menu);
+    if let Value::Object(fields) = menu {
+        fields.insert(CONTENT_HASH_FIELD.into(), Value::String(hash));
+    }
+}
+
+/// Whether the file still matches the hash it was saved with. A menu saved
+/// without one is an error, not a mismatch.
+#[tauri::command]
+pub fn verify_menu_integrity(path: String) -> Result<bool, String> {
+    let menu = read_json_file(Path::new(&path))?;
+    let stored = menu
+        .get(CONTENT_HASH_FIELD)
+        .and_then(Value::as_str)
+        .ok_or_else(|| io_err(format!("{path} has no {CONTENT_HASH_FIELD}")))?;
+    Ok(stored == content_hash(&menu))
+}
+
+// ---------- Slot operations ----------
+/// Copies a slot into the next empty slot after it. A ring with no empty
+/// slot is an error; the ring size is left for the user to change.
+#[tauri::command]
+pub fn duplicate_slice(mut menu: Value, index: usize) -> Result<Value, String> {
+    let items = items_mut(&mut menu)?;
+    check_index(items, index)?;
+    let copy = items[index].clone();
+    let len = items.len();
+    let target = (1..len)
+        .map(|offset| (index + offset) % len)
+        .find(|&i| is_empty_slot(&items[i]))
+        .ok_or_else(|| io_err(format!("no empty slot to duplicate slot {index} into")))?;
+    items[target] = copy;
+    validate_menu(&menu).map_err(validation_error)?;
+    Ok(menu)
+}
+
+#[tauri::command]
+pub fn swap_slices(mut menu: Value, a: usize, b: usize) -> Result<Value, String> {
+    let items = items_mut(&mut menu)?;
+    check_index(items, a)?;
+    check_index(items, b)?;
+    items.swap(a, b);
+    Ok(menu)
+}
+
+/// Moves every slot `steps` positions round the ring; positive steps go
+/// clockwise (towards higher indexes), negative ones the other way.
+#[tauri::command]
+pub fn rotate_slices(mut menu: Value, steps: i32) -> Result<Value, String> {
+    let items = items_mut(&mut menu)?;
+    if !items.is_empty() {
+        let len = items.len() as i64;
+        let shift = i64::from(steps).rem_euclid(len) as usize;
+        items.rotate_right(shift);
+    }
+    Ok(menu)
+}
+
+#[derive(Serialize)]
+pub struct Reindexed {
+    pub menu: Value,
+    /// Indices that more than one slice claimed. The first claimant keeps
+    /// it; the others move into the first free positions.
+    pub overlaps: Vec<usize>,
+}
+
+const INDEX_FIELD: &str = "index";
+
+/// Repairs hand-edited slice `index` fields: each slice goes to the position
+/// it declares, gaps become empty slices, and every slice is renumbered to
+/// its place in the ring. Slices that declare no index, or one already
+/// taken, fill the gaps in their original order. Menus where no slice
+/// declares an index only get padded up to MIN_SLOTS.
+#[tauri::command]
+pub fn reindex_slices(mut menu: Value) -> Result<Reindexed, String> {
+    let items = items_mut(&mut menu)?;
+    let declared = items.iter().any(|item| item.get(INDEX_FIELD).is_some());
+    let mut placed: Vec<Option<Value>> = vec![];
+    let mut floating = vec![];
+    let mut overlaps = vec![];
+    for item in items.drain(..) {
+        let Some(index) = item.get(INDEX_FIELD).and_then(Value::as_u64) else {
+            floating.push(item);
+            continue;
+        };
+        let index = index as usize;
+        if index >= MAX_SLOTS {
+            return Err(io_err(format!(
+                "slice index {index} is past the largest ring ({MAX_SLOTS} slots)"
+            )));
+        }
+        if placed.len() <= index {
+            placed.resize(index + 1, None);
+        }
+        if placed[index].is_some() {
+            if !overlaps.contains(&index) {
+                overlaps.push(index);
+            }
+            floating.push(item);
+        } else {
+            placed[index] = Some(item);
+        }
+    }
+
+    let mut floating = floating.into_iter();
+    for slot in placed.iter_mut().filter(|slot| slot.is_none()) {
+        *slot = floating.next();
+    }
+    items.extend(
+        placed
+            .into_iter()
+            .map(|slot| slot.unwrap_or_else(empty_slot)),
+    );
+    items.extend(floating);
+    if items.len() > MAX_SLOTS {
+        return Err(io_err(format!(
+            "menu has {} slices, more than a ring holds ({MAX_SLOTS})",
+            items.len()
+        )));
+    }
+    while items.len() < MIN_SLOTS {
+        items.push(empty_slot());
+    }
+    if declared {
+        for (i, item) in items.iter_mut().enumerate() {
+            if let Some(obj) = item.as_object_mut() {
+                obj.insert(INDEX_FIELD.into(), Value::from(i));
+            }
+        }
+    }
+    overlaps.sort_unstable();
+    Ok(Reindexed { menu, overlaps })
+}
+
+/// Whether a slot, or a menu at its top level, lists `tag` in `tags`.
+pub fn has_tag(item: &Value, tag: &str) -> bool {
+    item.get("tags")
+        .and_then(Value::as_array)
+        .map(|tags| tags.iter().any(|t| t.as_str() == Some(tag)))
+        .unwrap_or(false)
+}
+
+/// A copy of the menu holding only the slots tagged `tag`, in their original
+/// order. Short results are padded with empty slots up to MIN_SLOTS so the
+/// ring stays one the frontend can show.
+#[tauri::command]
+pub fn extract_tagged_slices(mut menu: Value, tag: String) -> Result<Value, String> {
+    let items = items_mut(&mut menu)?;
+    items.retain(|item| has_tag(item, &tag));
+    if items.is_empty() {
+        return Err(io_err(format!("no slots are tagged {tag}")));
+    }
+    while items.len() < MIN_SLOTS {
+        items.push(empty_slot());
+    }
+    validate_menu(&menu).map_err(validation_error)?;
+    Ok(menu)
+}
+
+/// Spreads the filled slots over as many menus as it takes, at most
+/// `chunk_size` each, in order. With more than one part, names get a
+/// ` (part N)` suffix and commands a `-part-N` one so Plasticity can tell
+/// the menus apart.
+#[tauri::command]
+pub fn split_menu(menu: Value, chunk_size: u32) -> Result<Vec<Value>, String> {
+    let size = chunk_size as usize;
+    if !(MIN_SLOTS..=MAX_SLOTS).contains(&size) {
+        return Err(io_err(format!(
+            "chunk_size must be between {MIN_SLOTS} and {MAX_SLOTS}"
+        )));
+    }
+    let filled: Vec<Value> = items(&menu)?
+        .iter()
+        .filter(|item| !is_empty_slot(item))
+        .cloned()
+        .collect();
+    if filled.is_empty() {
+        return Err(io_err("menu has no filled slots to split"));
+    }
+    let parts = filled.chunks(size).count();
+    let name = item_str(&menu, "name").to_string();
+    let command = item_str(&menu, "command").to_string();
+    let mut out = vec![];
+    for (n, chunk) in filled.chunks(size).enumerate() {
+        let mut part = menu.clone();
+        let slots = items_mut(&mut part)?;
+        *slots = chunk.to_vec();
+        while slots.len() < MIN_SLOTS {
+            slots.push(empty_slot());
+        }
+        if parts > 1 {
+            part["name"] = Value::String(format!("{name} (part {})", n + 1));
+            part["command"] = Value::String(format!("{command}-part-{}", n + 1));
+        }
+        validate_menu(&part).map_err(validation_error)?;
+        out.push(part);
+    }
+    Ok(out)
+}
+
+// ---------- Slice snippets ----------
+/// Saves one slot, as-is, to a `.slice.json` snippet for reuse in other
+/// menus.
+#[tauri::command]
+pub fn export_slice_snippet(menu: Value, index: usize, out_path: String) -> Result<(), String> {
+    let items = items(&menu)?;
+    check_index(items, index)?;
+    let slot = &items[index];
+    if is_empty_slot(slot) {
+        return Err(io_err(format!("slot {index} is empty")));
+    }
+    write_json_file(Path::new(&out_path), slot)
+}
+
+/// Puts a snippet into slot `target_index`. An occupied slot is only
+/// replaced with `overwrite`.
+#[tauri::command]
+pub fn import_slice_snippet(
+    mut menu: Value,
+    path: String,
+    target_index: usize,
+    overwrite: Option<bool>,
+) -> Result<Value, String> {
+    let snippet = read_json_file(Path::new(&path))?;
+    let items = items_mut(&mut menu)?;
+    check_index(items, target_index)?;
+    // Checked as the slot it's about to become.
+    let mut errors = vec![];
+    validate_item(target_index, &snippet, items.len(), &mut errors);
+    if is_empty_slot(&snippet) {
+        errors.push("snippet has no command".into());
+    }
+    if !errors.is_empty() {
+        return Err(io_err(format!(
+            "{path} is not a slice snippet: {}",
+            errors.join("; ")
+        )));
+    }
+    if !is_empty_slot(&items[target_index]) && !overwrite.unwrap_or(false) {
+        return Err(io_err(format!("slot {target_index} is already in use")));
+    }
+    items[target_index] = snippet;
+    Ok(menu)
+}
+
+// ---------- Diff ----------
+#[derive(Serialize)]
+pub struct SliceChange {
+    pub index: usize,
+    /// None when the slot only exists on the other side.
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Serialize)]
+pub struct MenuDiff {
+    pub name_changed: bool,
+    pub command_changed: bool,
+    pub slices: Vec<SliceChange>,
+}
+
+/// Slot-by-slot comparison by index, so a rotation shows up as every slot
+/// changing rather than as a move.
+pub fn diff_menus(before: &Value, after: &Value) -> Result<MenuDiff, String> {
+    let (old, new) = (items(before)?, items(after)?);
+    let slices = (0..old.len().max(new.len()))
+        .filter(|&i| old.get(i) != new.get(i))
+        .map(|index| SliceChange {
+            index,
+            before: old.get(index).cloned(),
+            after: new.get(index).cloned(),
+        })
+        .collect();
+    Ok(MenuDiff {
+        name_changed: before.get("name") != after.get("name"),
+        command_changed: before.get("command") != after.get("command"),
+        slices,
+    })
+}
+
+// ---------- Stats ----------
+#[derive(Serialize)]
+pub struct MenuStats {
+    pub total: usize,
+    pub filled: usize,
+    pub empty: usize,
+    pub distinct_commands: usize,
+    pub has_key_conflicts: bool,
+}
+
+#[tauri::command]
+pub fn menu_stats(menu: Value, app: tauri::AppHandle) -> Result<MenuStats, String> {
+    let items = items(&menu)?;
+    let filled = items.iter().filter(|item| !is_empty_slot(item)).count();
+    let distinct: HashSet<&str> = items
+        .iter()
+        .map(|item| item_str(item, "command"))
+        .filter(|c| !c.is_empty())
+        .collect();
+    let commands = palette::merged_commands(&app)?;
+    Ok(MenuStats {
+        total: items.len(),
+        filled,
+        empty: items.len() - filled,
+        distinct_commands: distinct.len(),
+        has_key_conflicts: !keys::keybinding_conflicts(&menu, &commands)?.is_empty(),
+    })
+}
+
+// ---------- Scripted edits ----------
+#[tauri::command]
+pub fn apply_json_patch(path: String, patch: Value) -> Result<Value, String> {
+    let path = Path::new(&path);
+    let mut menu = read_json_file(path)?;
+    let patch: json_patch::Patch =
+        serde_json::from_value(patch).map_err(|e| io_err(format!("invalid JSON patch: {e}")))?;
+    json_patch::patch(&mut menu, &patch).map_err(|e| io_err(format!("apply patch failed: {e}")))?;
+    validate_menu(&menu).map_err(validation_error)?;
+    write_json_file(path, &menu)?;
+    Ok(menu)
+}
+
+/// Writes the RFC-6902 patch that turns the on-disk menu into `modified`,
+/// for sharing just the changes; apply it with apply_json_patch.
+#[tauri::command]
+pub fn export_menu_patch(
+    base_path: String,
+    modified: Value,
+    out_path: String,
+) -> Result<(), String> {
+    let base = read_json_file(Path::new(&base_path))?;
+    let patch = json_patch::diff(&base, &modified);
+    let value =
+        serde_json::to_value(&patch).map_err(|e| io_err(format!("serialize patch failed: {e}")))?;
+    write_json_file(Path::new(&out_path), &value)
+}
+
+// ---------- Labels ----------
+/// Trims every slot label and, with `collapse_spaces`, squeezes inner runs
+/// of whitespace to one space. Returns how many labels changed.
+pub fn tidy_labels(menu: &mut Value, collapse_spaces: bool) -> usize {
+    let Ok(items) = items_mut(menu) else {
+        return 0;
+    };
+    let mut cleaned = 0;
+    for item in items {
+        let Some(Value::String(label)) = item.get_mut("label") else {
+            continue;
+        };
+        let tidy = if collapse_spaces {
+            label.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            label.trim().to_string()
+        };
+        if tidy != *label {
+            *label = tidy;
+            cleaned += 1;
+        }
+    }
+    cleaned
+}
+
+#[tauri::command]
+pub fn autofill_slice_labels(mut menu: Value, app: tauri::AppHandle) -> Result<Value, String> {
+    let commands = palette::merged_commands(&app)?;
+    for item in items_mut(&mut menu)? {
+        let command = item_str(item, "command");
+        if command.is_empty() || !item_str(item, "label").trim().is_empty() {
+            continue;
+        }
+        if let Some(entry) = commands.get(command) {
+            let label = palette::entry_name(command, entry);
+            if let Some(obj) = item.as_object_mut() {
+                obj.insert("label".into(), Value::String(label));
+            }
+        }
+    }
+    Ok(menu)
+}
+
+/// Default for the label_limit preference. Plasticity doesn't document a
+/// maximum, so this is a conservative guess rather than a hard limit; the
+/// preference lets users match whatever their install actually shows.
+pub const DEFAULT_LABEL_LIMIT: usize = 20;
+
+#[derive(Serialize)]
+pub struct LabelIssue {
+    pub index: usize,
+    pub label: String,
+    /// In grapheme clusters, so an emoji or accented letter counts once.
+    pub length: usize,
+}
+
+#[tauri::command]
+pub fn find_overlong_labels(
+    menu: Value,
+    max_len: Option<usize>,
+) -> Result<Vec<LabelIssue>, String> {
+    let max_len = max_len.unwrap_or_else(|| prefs::active().label_limit);
+    Ok(items(&menu)?
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            let label = item_str(item, "label");
+            let length = label.graphemes(true).count();
+            (length > max_len).then(|| LabelIssue {
+                index,
+                label: label.to_string(),
+                length,
+            })
+        })
+        .collect())
+}
+
+// ---------- Icons ----------
+#[derive(Serialize)]
+pub struct IconIssue {
+    pub index: usize,
+    pub icon: String,
+}
+
+/// Absolute on this OS, or a Windows drive/UNC path, since a menu written on
+/// Windows is just as unportable when opened elsewhere.
+fn is_absolute_icon(icon: &str) -> bool {
+    let b = icon.as_bytes();
+    let drive =
+        b.len() >= 3 && b[0].is_ascii_alphabetic() && b[1] == b':' && matches!(b[2], b'\\' | b'/');
+    Path::new(icon).is_absolute() || drive || icon.starts_with("\\\\")
+}
+
+#[tauri::command]
+pub fn find_absolute_icon_paths(menu: Value) -> Result<Vec<IconIssue>, String> {
+    Ok(items(&menu)?
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| is_absolute_icon(item_str(item, "icon")))
+        .map(|(index, item)| IconIssue {
+            index,
+            icon: item_str(item, "icon").to_string(),
+        })
+        .collect())
+}
+
+#[derive(Serialize)]
+pub struct Relativized {
+    pub menu: Value,
+    pub warnings: Vec<String>,
+}
+
+/// Rewrites absolute icon paths under `base_dir` as forward-slash paths
+/// relative to it. Ones outside it are left as they are, with a warning.
+#[tauri::command]
+pub fn relativize_icon_paths(mut menu: Value, base_dir: String) -> Result<Relativized, String> {
+    let base = Path::new(&base_dir);
+    let mut warnings = vec![];
+    for (i, item) in items_mut(&mut menu)?.iter_mut().enumerate() {
+        let icon = item_str(item, "icon").to_string();
+        if !is_absolute_icon(&icon) {
+            continue;
+        }
+        match Path::new(&icon).strip_prefix(base) {
+            Ok(rel) => {
+                let parts: Vec<_> = rel
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .collect();
+                item["icon"] = Value::String(parts.join("/"));
+            }
+            Err(_) => warnings.push(format!("slot {i}: icon {icon} is not under {base_dir}")),
+        }
+    }
+    Ok(Relativized { menu, warnings })
+}
+
+/// What Plasticity renders as a slot icon.
+pub const DEFAULT_ICON_FORMATS: [&str; 2] = ["png", "svg"];
+
+#[derive(Serialize)]
+pub struct IconFormatIssue {
+    pub index: usize,
+    pub icon: String,
+    /// Format read from the file's content, when it could be read and
+    /// recognized.
+    pub detected: Option<String>,
+    pub reason: String,
+}
+
+const SNIFFED_FORMATS: [&str; 7] = ["png", "jpg", "gif", "webp", "bmp", "ico", "svg"];
+
+// Sniffs the first bytes; SVG is text, so it's recognized by an `<svg`
+// tag near the start.
+fn sniff_image_format(head: &[u8]) -> Option<&'static str> {
+    let format = match head {
+        [0x89, b'P', b'N', b'G', ..] => "png",
+        [0xFF, 0xD8, 0xFF, ..] => "jpg",
+        [b'G', b'I', b'F', b'8', ..] => "gif",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "webp",
+        [b'B', b'M', ..] => "bmp",
+        [0, 0, 1, 0, ..] => "ico",
+        _ if String::from_utf8_lossy(head).contains("<svg") => "svg",
+        _ => return None,
+    };
+    Some(format)
+}
+
+fn canonical_format(ext: &str) -> String {
+    match ext.to_ascii_lowercase().as_str() {
+        "jpeg" => "jpg".into(),
+        other => other.into(),
+    }
+}
+
+/// Slots whose icon file Plasticity likely won't render: an extension
+/// outside `allowed` (DEFAULT_ICON_FORMATS when empty), or content that's
+/// really some other format, like a PNG saved as `.svg`. Relative icons
+/// resolve against `base_dir`. Icons without an extension are taken to be
+/// names rather than files and skipped.
+#[tauri::command]
+pub fn validate_icon_formats(
+    menu: Value,
+    base_dir: String,
+    allowed: Vec<String>,
+) -> Result<Vec<IconFormatIssue>, String> {
+    let allowed: HashSet<String> = if allowed.is_empty() {
+        DEFAULT_ICON_FORMATS.iter().map(|f| f.to_string()).collect()
+    } else {
+        allowed
+            .iter()
+            .map(|f| canonical_format(f.trim().trim_start_matches('.')))
+            .collect()
+    };
+    let base = Path::new(&base_dir);
+    let mut issues = vec![];
+    for (index, item) in items(&menu)?.iter().enumerate() {
+        let icon = item_str(item, "icon");
+        let Some(ext) = Path::new(icon).extension() else {
+            continue;
+        };
+        let ext = canonical_format(&ext.to_string_lossy());
+        let path = base.join(icon);
+        let detected = fs::File::open(&path).ok().and_then(|mut file| {
+            let mut head = [0u8; 1024];
+            let n = file.read(&mut head).ok()?;
+            sniff_image_format(&head[..n])
+        });
+        let reason = if !path.is_file() {
+            Some("file not found".to_string())
+        } else if !allowed.contains(&ext) {
+            Some(format!(".{ext} is not an allowed icon format"))
+        } else {
+            match detected {
+                Some(found) if found != ext => {
+                    Some(format!("named .{ext} but the file is {found}"))
+                }
+                None if SNIFFED_FORMATS.contains(&ext.as_str()) => {
+                    Some(format!("content isn't a valid .{ext} image"))
+                }
+                _ => None,
+            }
+        };
+        if let Some(reason) = reason {
+            issues.push(IconFormatIssue {
+                index,
+                icon: icon.to_string(),
+                detected: detected.map(str::to_string),
+                reason,
+            });
+        }
+    }
+    Ok(issues)
+}
+
+// ---------- Clipboard ----------
+#[tauri::command]
+pub fn copy_menu_to_clipboard(menu: Value, app: tauri::AppHandle) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(&menu)
+        .map_err(|e| io_err(format!("serialize json failed: {e}")))?;
+    app.clipboard()
+        .write_text(text)
+        .map_err(|e| io_err(format!("write clipboard failed: {e}")))
+}
+
+#[tauri::command]
+pub fn paste_menu_from_clipboard(app: tauri::AppHandle) -> Result<Value, String> {
+    let text = app
+        .clipboard()
+        .read_text()
+        .map_err(|e| io_err(format!("read clipboard failed: {e}")))?;
+    let menu: Value = serde_json::from_str(&text)
+        .map_err(|e| io_err(format!("clipboard does not hold JSON: {e}")))?;
+    validate_menu(&menu).map_err(validation_error)?;
+    Ok(menu)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn menu_with_labels(labels: &[&str]) -> Value {
+        let items: Vec<Value> = labels
+            .iter()
+            .map(|label| json!({ "command": "command:test", "icon": "", "label": label }))
+            .collect();
+        json!({ "name": "Test", "command": "default-menu:test", "items": items })
+    }
+
+    #[test]
+    fn overlong_labels_count_graphemes_at_the_limit() {
+        // 20 graphemes but 60 bytes: each "é" is e + U+0301.
+        let at_limit = "e\u{301}".repeat(20);
+        // 21 graphemes; the flag is a single cluster of two code points.
+        let over_limit = format!("{}\u{1F1EF}\u{1F1F5}", "a".repeat(20));
+        let menu = menu_with_labels(&[&at_limit, &over_limit]);
+
+        let issues = find_overlong_labels(menu, Some(DEFAULT_LABEL_LIMIT)).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].index, 1);
+        assert_eq!(issues[0].length, 21);
+    }
+
+    #[test]
+    fn overlong_labels_respect_a_custom_limit() {
+        let menu = menu_with_labels(&["abcde", "abcdef"]);
+        let issues = find_overlong_labels(menu, Some(5)).unwrap();
+        assert_eq!(issues.iter().map(|i| i.index).collect::<Vec<_>>(), [1]);
+    }
+}
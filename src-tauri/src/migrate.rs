@@ -0,0 +1,92 @@
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::{backups, io_err, library, menu, write_json_file};
+
+/// Builder schema version stamped into menus as `schema_version`. Files
+/// without it predate versioning and count as version 0.
+pub const SCHEMA_VERSION: u64 = 1;
+const VERSION_FIELD: &str = "schema_version";
+
+// MIGRATIONS[n] upgrades a menu from version n to n + 1.
+const MIGRATIONS: [fn(&mut Map<String, Value>); SCHEMA_VERSION as usize] = [to_v1];
+
+// v1: every slot carries string command/icon/label fields, so the frontend
+// and exporters never have to guess at missing or null ones.
+fn to_v1(menu: &mut Map<String, Value>) {
+    let Some(Value::Array(items)) = menu.get_mut("items") else {
+        return;
+    };
+    for item in items {
+        let Value::Object(fields) = item else {
+            continue;
+        };
+        for field in ["command", "icon", "label"] {
+            let slot = fields
+                .entry(field)
+                .or_insert_with(|| Value::String(String::new()));
+            if slot.is_null() {
+                *slot = Value::String(String::new());
+            }
+        }
+    }
+}
+
+/// Brings a menu up to SCHEMA_VERSION, stamping the version only when a
+/// migration changed something. Menus from a newer build are refused rather
+/// than silently downgraded.
+pub fn migrate_menu(menu: Value) -> Result<Value, String> {
+    let Value::Object(mut obj) = menu else {
+        return Err(io_err("menu must be a JSON object"));
+    };
+    let version = match obj.get(VERSION_FIELD) {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| io_err(format!("{VERSION_FIELD} must be a whole number")))?,
+    };
+    if version > SCHEMA_VERSION {
+        return Err(io_err(format!(
+            "menu uses schema {version}, this build only knows up to {SCHEMA_VERSION}"
+        )));
+    }
+    let before = obj.clone();
+    for step in &MIGRATIONS[version as usize..] {
+        step(&mut obj);
+    }
+    // A menu the steps didn't touch is already in the current shape;
+    // stamping it anyway would make every load look like an edit.
+    if obj != before {
+        obj.insert(VERSION_FIELD.into(), Value::from(SCHEMA_VERSION));
+    }
+    Ok(Value::Object(obj))
+}
+
+/// Migrates every menu in the library and, unless `dry_run`, writes back
+/// the ones that changed (backing up the old file first). Returns the
+/// relative paths that were, or would be, updated. Files that don't parse
+/// or don't validate after migrating are left alone.
+#[tauri::command]
+pub fn migrate_directory(directory: String, dry_run: bool) -> Result<Vec<String>, String> {
+    let dir = Path::new(&directory);
+    let mut updated = vec![];
+    for entry in library::load_library(dir)? {
+        let Ok(original) = entry.parsed else {
+            continue;
+        };
+        let Ok(migrated) = migrate_menu(original.clone()) else {
+            continue;
+        };
+        if migrated == original || menu::validate_menu(&migrated).is_err() {
+            continue;
+        }
+        if !dry_run {
+            let path = dir.join(&entry.rel);
+            backups::backup_existing(&path)?;
+            write_json_file(&path, &migrated)?;
+        }
+        updated.push(entry.rel);
+    }
+    Ok(updated)
+}
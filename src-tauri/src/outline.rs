@@ -0,0 +1,164 @@
+use std::{fs, path::Path};
+
+use serde::Serialize;
+
+use crate::{fmt_path, io_err};
+
+#[derive(Serialize)]
+pub struct OutlineNode {
+    /// JSON pointer to the value; the root is "".
+    pub pointer: String,
+    /// "object", "array", "string", "number", "bool" or "null".
+    pub kind: &'static str,
+    /// 1-based lines the value starts and ends on; they differ only for
+    /// objects and arrays spread over several lines, which are foldable.
+    pub line: usize,
+    pub end_line: usize,
+}
+
+fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+// Walks text serde_json has already accepted, so it only has to track
+// positions, not diagnose bad input; running off the end is the one error.
+struct Scanner<'a> {
+    text: &'a str,
+    pos: usize,
+    line: usize,
+    nodes: Vec<OutlineNode>,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(b) = self.peek() {
+            match b {
+                b'\n' => self.line += 1,
+                b' ' | b'\t' | b'\r' => {}
+                _ => return,
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn expect_more(&self) -> Result<u8, String> {
+        self.peek()
+            .ok_or_else(|| io_err("unexpected end of JSON while outlining"))
+    }
+
+    // Returns the raw quoted slice, escapes and all.
+    fn string(&mut self) -> Result<&str, String> {
+        let start = self.pos;
+        self.pos += 1;
+        loop {
+            match self.expect_more()? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Ok(&self.text[start..self.pos]);
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn value(&mut self, pointer: String) -> Result<(), String> {
+        self.skip_ws();
+        let line = self.line;
+        let slot = self.nodes.len();
+        let kind = match self.expect_more()? {
+            b'{' => "object",
+            b'[' => "array",
+            b'"' => "string",
+            b't' | b'f' => "bool",
+            b'n' => "null",
+            _ => "number",
+        };
+        self.nodes.push(OutlineNode {
+            pointer: pointer.clone(),
+            kind,
+            line,
+            end_line: line,
+        });
+        match kind {
+            "object" => {
+                self.pos += 1;
+                loop {
+                    self.skip_ws();
+                    match self.expect_more()? {
+                        b'}' => break,
+                        b',' => {
+                            self.pos += 1;
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    let raw = self.string()?;
+                    let key: String = serde_json::from_str(raw)
+                        .map_err(|e| io_err(format!("bad key while outlining: {e}")))?;
+                    self.skip_ws();
+                    // The colon.
+                    self.pos += 1;
+                    self.value(format!("{pointer}/{}", escape_token(&key)))?;
+                }
+                self.pos += 1;
+            }
+            "array" => {
+                self.pos += 1;
+                let mut index = 0;
+                loop {
+                    self.skip_ws();
+                    match self.expect_more()? {
+                        b']' => break,
+                        b',' => {
+                            self.pos += 1;
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    self.value(format!("{pointer}/{index}"))?;
+                    index += 1;
+                }
+                self.pos += 1;
+            }
+            "string" => {
+                self.string()?;
+            }
+            _ => {
+                while let Some(b) = self.peek() {
+                    if !(b.is_ascii_alphanumeric() || matches!(b, b'-' | b'+' | b'.')) {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            }
+        }
+        self.nodes[slot].end_line = self.line;
+        Ok(())
+    }
+}
+
+/// Every value in the file in document order, with its pointer, type and
+/// line span, for the source view's outline and folding. The file must be
+/// valid JSON; the parse error is returned otherwise.
+#[tauri::command]
+pub fn outline_menu_json(path: String) -> Result<Vec<OutlineNode>, String> {
+    let path = Path::new(&path);
+    let text = fs::read_to_string(path)
+        .map_err(|e| io_err(format!("read {} failed: {e}", fmt_path(path))))?;
+    let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+    serde_json::from_str::<serde_json::Value>(text)
+        .map_err(|e| io_err(format!("parse {} failed: {e}", fmt_path(path))))?;
+    let mut scanner = Scanner {
+        text,
+        pos: 0,
+        line: 1,
+        nodes: vec![],
+    };
+    scanner.value(String::new())?;
+    Ok(scanner.nodes)
+}
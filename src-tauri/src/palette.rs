@@ -0,0 +1,658 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{
+    app_data_dir, backups, fmt_path, io_err, read_json_file, write_file_atomic, write_json_file,
+};
+
+// The guaranteed fallback when the user has no commands file of their own.
+pub const EMBEDDED_COMMANDS: &str = include_str!("../../dist/commands.json");
+
+// ---------- Locations ----------
+pub fn user_commands_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_data_dir(app)?.join("commands.json"))
+}
+
+pub fn overlay_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_data_dir(app)?.join("commands.overlay.json"))
+}
+
+/// Seeds app_data_dir with a copy of the embedded commands so customizers
+/// have a concrete file to edit. Leaves an existing user file alone.
+#[tauri::command]
+pub fn ensure_user_commands(app: tauri::AppHandle) -> Result<String, String> {
+    let path = user_commands_path(&app)?;
+    if !path.exists() {
+        write_file_atomic(&path, EMBEDDED_COMMANDS.as_bytes())?;
+    }
+    Ok(fmt_path(&path))
+}
+
+/// Moves the user's commands file and overlay aside to `.bak` so loading
+/// falls back to the shipped set. Missing files are fine.
+#[tauri::command]
+pub fn reset_commands_to_default(app: tauri::AppHandle) -> Result<(), String> {
+    for path in [user_commands_path(&app)?, overlay_path(&app)?] {
+        if !path.exists() {
+            continue;
+        }
+        let mut bak = path.clone().into_os_string();
+        bak.push(".bak");
+        let bak = PathBuf::from(bak);
+        fs::rename(&path, &bak)
+            .map_err(|e| io_err(format!("back up {} failed: {e}", fmt_path(&path))))?;
+    }
+    Ok(())
+}
+
+// ---------- Loading ----------
+pub fn embedded_commands() -> Result<Value, String> {
+    serde_json::from_str(EMBEDDED_COMMANDS)
+        .map_err(|e| io_err(format!("embedded commands.json parse failed: {e}")))
+}
+
+/// Self-test for the fallback every other source degrades to: the embedded
+/// bytes must parse and each entry must be an icon string or an object whose
+/// known fields are strings. All problems are reported together.
+#[tauri::command]
+pub fn check_embedded_commands() -> Result<(), String> {
+    let value: Value = serde_json::from_str(EMBEDDED_COMMANDS)
+        .map_err(|e| io_err(format!("embedded commands.json does not parse: {e}")))?;
+    let Value::Object(entries) = value else {
+        return Err(io_err("embedded commands.json must be a JSON object"));
+    };
+    if entries.is_empty() {
+        return Err(io_err("embedded commands.json has no commands"));
+    }
+    let mut errors = vec![];
+    for (id, entry) in &entries {
+        if id.trim().is_empty() {
+            errors.push("empty command id".to_string());
+        }
+        match entry {
+            Value::String(_) => {}
+            Value::Object(fields) => {
+                for field in ENTRY_FIELDS {
+                    if fields.get(field).map(|v| !v.is_string()).unwrap_or(false) {
+                        errors.push(format!("{id}: {field} must be a string"));
+                    }
+                }
+            }
+            _ => errors.push(format!("{id}: must be an icon string or an object")),
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(io_err(format!(
+            "embedded commands.json is invalid: {}",
+            errors.join("; ")
+        )))
+    }
+}
+
+/// Where load_commands gets its base set from: "user" (app_data_dir) or
+/// "embedded", with "+overlay" when an overlay is merged on top.
+pub fn commands_source(app: &tauri::AppHandle) -> Result<String, String> {
+    let base = if user_commands_path(app)?.exists() {
+        "user"
+    } else {
+        "embedded"
+    };
+    Ok(if overlay_path(app)?.exists() {
+        format!("{base}+overlay")
+    } else {
+        base.to_string()
+    })
+}
+
+/// The user's commands.json if they have one, else the embedded set.
+fn base_commands(user: &Path) -> Result<Value, String> {
+    if user.exists() {
+        read_json_file(user)
+    } else {
+        embedded_commands()
+    }
+}
+
+/// Base commands with the user's overlay applied on top. Every entry is
+/// normalized to an object so callers can read `icon`, `name`, `category`
+/// and `keybinding` without caring which file it came from.
+pub fn merged_commands(app: &tauri::AppHandle) -> Result<Map<String, Value>, String> {
+    merge_commands(&user_commands_path(app)?, &overlay_path(app)?)
+}
+
+// merged_commands for explicit file locations. Base entries keep their
+// order; overlay entries are applied in file order, each one's fields
+// replacing the same fields before it, and new ids go at the end.
+fn merge_commands(user: &Path, overlay: &Path) -> Result<Map<String, Value>, String> {
+    let base = base_commands(user)?;
+    let Value::Object(base) = base else {
+        return Err(io_err("commands file must be a JSON object"));
+    };
+    let mut merged: Map<String, Value> = base
+        .into_iter()
+        .map(|(id, entry)| (id, normalize_entry(entry)))
+        .collect();
+
+    if overlay.exists() {
+        let Value::Array(entries) = read_json_file(overlay)? else {
+            return Err(io_err(format!(
+                "{} must be a JSON array",
+                fmt_path(overlay)
+            )));
+        };
+        for entry in entries {
+            let Value::Object(mut fields) = entry else {
+                continue;
+            };
+            let Some(Value::String(id)) = fields.shift_remove("id") else {
+                continue;
+            };
+            let slot = merged
+                .entry(id)
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(existing) = slot {
+                existing.extend(fields);
+            }
+        }
+    }
+    Ok(merged)
+}
+
+fn normalize_entry(entry: Value) -> Value {
+    match entry {
+        Value::Object(_) => entry,
+        Value::String(icon) => {
+            let mut obj = Map::new();
+            obj.insert("icon".into(), Value::String(icon));
+            Value::Object(obj)
+        }
+        _ => Value::Object(Map::new()),
+    }
+}
+
+/// Merged commands bucketed by `category`, categories in alphabetical order
+/// and entries in merged order within each. Each entry carries its `id`.
+#[tauri::command]
+pub fn load_commands_grouped(app: tauri::AppHandle) -> Result<Value, String> {
+    let mut groups: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+    for (id, mut entry) in merged_commands(&app)? {
+        let category = entry_category(&entry).unwrap_or(UNCATEGORIZED).to_string();
+        if let Value::Object(obj) = &mut entry {
+            obj.insert("id".into(), Value::String(id));
+        }
+        groups.entry(category).or_default().push(entry);
+    }
+    Ok(Value::Object(
+        groups
+            .into_iter()
+            .map(|(category, entries)| (category, Value::Array(entries)))
+            .collect(),
+    ))
+}
+
+// ---------- Menu checks ----------
+/// Nested menus are referenced as `view:radial:<menu command>` and resolve
+/// against other menu files, not the commands set.
+pub const NESTED_MENU_PREFIX: &str = "view:radial:";
+
+/// Command ids the menu references that aren't in `commands`, in slot order
+/// without repeats.
+pub fn unknown_command_ids(
+    menu: &Value,
+    commands: &Map<String, Value>,
+) -> Result<Vec<String>, String> {
+    let mut unknown: Vec<String> = vec![];
+    for item in crate::menu::items(menu)? {
+        let id = crate::menu::item_str(item, "command");
+        if id.is_empty() || id.starts_with(NESTED_MENU_PREFIX) || commands.contains_key(id) {
+            continue;
+        }
+        if !unknown.iter().any(|u| u == id) {
+            unknown.push(id.to_string());
+        }
+    }
+    Ok(unknown)
+}
+
+/// Checks the menu against the same commands load_commands serves: the
+/// user's commands.json, or the embedded set on a fresh install, with the
+/// overlay on top. Returns the unknown ids; a command bound to more than one
+/// slot is allowed, but usually a slip, so it's logged as a warning.
+#[tauri::command]
+pub fn check_menu_against_commands(
+    menu: Value,
+    app: tauri::AppHandle,
+) -> Result<Vec<String>, String> {
+    let commands = merged_commands(&app)?;
+    let unknown = unknown_command_ids(&menu, &commands)?;
+    let mut by_command: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, item) in crate::menu::items(&menu)?.iter().enumerate() {
+        let id = crate::menu::item_str(item, "command");
+        if !id.is_empty() {
+            by_command.entry(id).or_default().push(i);
+        }
+    }
+    for (command, slots) in by_command.iter().filter(|(_, slots)| slots.len() > 1) {
+        tracing::warn!("{command} is bound to more than one slot ({slots:?})");
+    }
+    Ok(unknown)
+}
+
+// ---------- Overlay checks ----------
+/// Why the overlay can't be used. Parse errors carry the 1-based position
+/// serde_json reports; shape errors leave it out.
+#[derive(Serialize, Clone)]
+pub struct OverlayError {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl OverlayError {
+    fn shape(message: String) -> Self {
+        OverlayError {
+            message,
+            line: None,
+            column: None,
+        }
+    }
+}
+
+/// A missing overlay is valid (there's nothing to merge); otherwise it must
+/// be an array of objects that each carry a string `id`.
+pub fn check_overlay(path: &Path) -> Result<(), OverlayError> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(OverlayError::shape(format!(
+                "read {} failed: {e}",
+                fmt_path(path)
+            )))
+        }
+    };
+    let value: Value = serde_json::from_str(&text).map_err(|e| OverlayError {
+        message: e.to_string(),
+        line: Some(e.line()),
+        column: Some(e.column()),
+    })?;
+    let Value::Array(entries) = value else {
+        return Err(OverlayError::shape("overlay must be a JSON array".into()));
+    };
+    for (i, entry) in entries.iter().enumerate() {
+        if !entry.get("id").map(Value::is_string).unwrap_or(false) {
+            return Err(OverlayError::shape(format!("entry {i}: missing string id")));
+        }
+    }
+    Ok(())
+}
+
+/// Entry fields merged_commands understands; anything else is merged but
+/// never read.
+const ENTRY_FIELDS: [&str; 4] = ["icon", "name", "category", "keybinding"];
+
+/// Lints the overlay entry by entry: missing or non-string ids, unknown
+/// fields, and ids that appear more than once. Empty for a clean (or
+/// missing) overlay.
+#[tauri::command]
+pub fn validate_commands_overlay(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    let path = overlay_path(&app)?;
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let Value::Array(entries) = read_json_file(&path)? else {
+        return Ok(vec!["overlay must be a JSON array".into()]);
+    };
+    let mut issues = vec![];
+    let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let Some(fields) = entry.as_object() else {
+            issues.push(format!("entry {i}: must be an object"));
+            continue;
+        };
+        match fields.get("id") {
+            Some(Value::String(id)) => match seen.get(id.as_str()) {
+                Some(first) => issues.push(format!(
+                    "entry {i}: duplicate id {id} (first at entry {first})"
+                )),
+                None => {
+                    seen.insert(id, i);
+                }
+            },
+            Some(_) => issues.push(format!("entry {i}: id must be a string")),
+            None => issues.push(format!("entry {i}: missing id")),
+        }
+        for key in fields.keys() {
+            if key != "id" && !ENTRY_FIELDS.contains(&key.as_str()) {
+                issues.push(format!("entry {i}: unknown field {key}"));
+            }
+        }
+    }
+    Ok(issues)
+}
+
+// ---------- CSV ----------
+const CSV_COLUMNS: [&str; 5] = ["id", "name", "category", "keybinding", "icon"];
+
+/// The merged commands as a spreadsheet, one row per id.
+#[tauri::command]
+pub fn export_commands_csv(out_path: String, app: tauri::AppHandle) -> Result<(), String> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    let csv_err = |e: csv::Error| io_err(format!("write csv failed: {e}"));
+    writer.write_record(CSV_COLUMNS).map_err(csv_err)?;
+    for (id, entry) in merged_commands(&app)? {
+        let field = |name: &str| {
+            entry
+                .get(name)
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .to_string()
+        };
+        writer
+            .write_record([
+                id.clone(),
+                field("name"),
+                field("category"),
+                field("keybinding"),
+                field("icon"),
+            ])
+            .map_err(csv_err)?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| io_err(format!("write csv failed: {e}")))?;
+    write_file_atomic(Path::new(&out_path), &bytes)
+}
+
+/// Reads a spreadsheet back into the overlay shape for the UI to review;
+/// nothing is saved. Columns are matched by header, `id` is required and
+/// the rest of CSV_COLUMNS are optional. Blank cells are left out.
+#[tauri::command]
+pub fn import_commands_csv(path: String) -> Result<Value, String> {
+    let mut reader =
+        csv::Reader::from_path(&path).map_err(|e| io_err(format!("open {path} failed: {e}")))?;
+    let headers = reader
+        .headers()
+        .map_err(|e| io_err(format!("read {path} failed: {e}")))?
+        .clone();
+    let columns: Vec<Option<&str>> = headers
+        .iter()
+        .map(|h| {
+            CSV_COLUMNS
+                .iter()
+                .copied()
+                .find(|c| c.eq_ignore_ascii_case(h.trim()))
+        })
+        .collect();
+    if !columns.contains(&Some("id")) {
+        return Err(io_err(format!("{path} has no id column")));
+    }
+    let mut entries = vec![];
+    let mut missing_ids = vec![];
+    for (i, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| io_err(format!("read {path} failed: {e}")))?;
+        let mut entry = Map::new();
+        for (column, cell) in columns.iter().zip(record.iter()) {
+            let cell = cell.trim();
+            if let Some(column) = column.filter(|_| !cell.is_empty()) {
+                entry.insert(column.to_string(), Value::String(cell.to_string()));
+            }
+        }
+        if entry.contains_key("id") {
+            entries.push(Value::Object(entry));
+        } else {
+            // Row 1 is the header.
+            missing_ids.push((i + 2).to_string());
+        }
+    }
+    if !missing_ids.is_empty() {
+        return Err(io_err(format!(
+            "rows without an id: {}",
+            missing_ids.join(", ")
+        )));
+    }
+    Ok(Value::Array(entries))
+}
+
+// ---------- Reference diff ----------
+#[derive(Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub reference: Option<Value>,
+    pub local: Option<Value>,
+}
+
+#[derive(Serialize)]
+pub struct CommandChange {
+    pub id: String,
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Serialize)]
+pub struct CommandsDiff {
+    /// Ids the local set has that the reference doesn't.
+    pub added: Vec<String>,
+    /// Ids the reference has that are missing locally.
+    pub removed: Vec<String>,
+    pub changed: Vec<CommandChange>,
+}
+
+/// Compares the merged local commands with a reference commands file (same
+/// shape as commands.json). Entries are normalized on both sides first, so a
+/// bare icon string equals `{"icon": ...}`. Ids come back sorted.
+#[tauri::command]
+pub fn diff_commands(
+    reference_path: String,
+    app: tauri::AppHandle,
+) -> Result<CommandsDiff, String> {
+    let Value::Object(reference) = read_json_file(Path::new(&reference_path))? else {
+        return Err(io_err(format!(
+            "{} must be a JSON object",
+            fmt_path(Path::new(&reference_path))
+        )));
+    };
+    let reference: BTreeMap<String, Value> = reference
+        .into_iter()
+        .map(|(id, entry)| (id, normalize_entry(entry)))
+        .collect();
+    let local: BTreeMap<String, Value> = merged_commands(&app)?.into_iter().collect();
+
+    let added = local
+        .keys()
+        .filter(|id| !reference.contains_key(*id))
+        .cloned()
+        .collect();
+    let removed = reference
+        .keys()
+        .filter(|id| !local.contains_key(*id))
+        .cloned()
+        .collect();
+    let mut changed = vec![];
+    for (id, theirs) in &reference {
+        let Some(ours) = local.get(id) else {
+            continue;
+        };
+        let (Value::Object(theirs), Value::Object(ours)) = (theirs, ours) else {
+            continue;
+        };
+        let names: BTreeSet<&String> = theirs.keys().chain(ours.keys()).collect();
+        let fields: Vec<FieldChange> = names
+            .into_iter()
+            .filter(|name| theirs.get(*name) != ours.get(*name))
+            .map(|name| FieldChange {
+                field: name.clone(),
+                reference: theirs.get(name).cloned(),
+                local: ours.get(name).cloned(),
+            })
+            .collect();
+        if !fields.is_empty() {
+            changed.push(CommandChange {
+                id: id.clone(),
+                fields,
+            });
+        }
+    }
+    Ok(CommandsDiff {
+        added,
+        removed,
+        changed,
+    })
+}
+
+// ---------- Renames ----------
+/// Points every overlay entry for `old_id` at `new_id`, backing the overlay
+/// up first. Returns how many entries matched; with `dry_run`, or when none
+/// do, nothing is written.
+pub fn rename_overlay_id(
+    app: &tauri::AppHandle,
+    old_id: &str,
+    new_id: &str,
+    dry_run: bool,
+) -> Result<usize, String> {
+    let path = overlay_path(app)?;
+    if !path.exists() {
+        return Ok(0);
+    }
+    let mut overlay = read_json_file(&path)?;
+    let Value::Array(entries) = &mut overlay else {
+        return Err(io_err(format!("{} must be a JSON array", fmt_path(&path))));
+    };
+    let mut renamed = 0;
+    for entry in entries {
+        let Some(id) = entry.get_mut("id") else {
+            continue;
+        };
+        if id.as_str() == Some(old_id) {
+            *id = Value::String(new_id.to_string());
+            renamed += 1;
+        }
+    }
+    if renamed > 0 && !dry_run {
+        backups::backup_existing(&path)?;
+        write_json_file(&path, &overlay)?;
+    }
+    Ok(renamed)
+}
+
+// ---------- Entry fields ----------
+pub const UNCATEGORIZED: &str = "Uncategorized";
+
+pub fn entry_category(entry: &Value) -> Option<&str> {
+    entry
+        .get("category")
+        .and_then(Value::as_str)
+        .filter(|c| !c.trim().is_empty())
+}
+
+pub fn entry_icon(entry: &Value) -> Option<&str> {
+    entry.get("icon").and_then(Value::as_str)
+}
+
+pub fn entry_keybinding(entry: &Value) -> Option<&str> {
+    entry
+        .get("keybinding")
+        .and_then(Value::as_str)
+        .filter(|k| !k.trim().is_empty())
+}
+
+/// The entry's `name`, or a label derived from the id the same way the
+/// frontend's makeLabelFromCommand does.
+pub fn entry_name(id: &str, entry: &Value) -> String {
+    match entry.get("name").and_then(Value::as_str) {
+        Some(name) if !name.trim().is_empty() => name.to_string(),
+        _ => label_from_command(id),
+    }
+}
+
+pub fn label_from_command(id: &str) -> String {
+    let src = id.strip_prefix(NESTED_MENU_PREFIX).unwrap_or(id);
+    let parts: Vec<&str> = src.split(':').collect();
+    let raw = if parts.len() > 1 { parts[1] } else { parts[0] };
+    raw.split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn missing_user_commands_fall_back_to_the_embedded_set() {
+        let dir = tempfile::tempdir().unwrap();
+        let merged = merge_commands(
+            &dir.path().join("commands.json"),
+            &dir.path().join("commands.overlay.json"),
+        )
+        .unwrap();
+
+        let Value::Object(embedded) = embedded_commands().unwrap() else {
+            panic!("embedded commands.json is not an object");
+        };
+        assert_eq!(merged.len(), embedded.len());
+        assert!(merged.keys().eq(embedded.keys()));
+        assert_eq!(merged["command:align"], json!({ "icon": "align" }));
+
+        // A fresh install still tells known commands from unknown ones.
+        let menu = json!({
+            "name": "Test",
+            "command": "default-menu:test",
+            "items": [
+                { "command": "command:align", "icon": "", "label": "Align" },
+                { "command": "command:not-a-command", "icon": "", "label": "Nope" }
+            ]
+        });
+        assert_eq!(
+            unknown_command_ids(&menu, &merged).unwrap(),
+            ["command:not-a-command"]
+        );
+    }
+
+    #[test]
+    fn overlay_entries_merge_over_the_base_in_file_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("commands.json");
+        let overlay = dir.path().join("commands.overlay.json");
+        let base = json!({
+            "command:b": "b-icon",
+            "command:a": { "icon": "a-icon", "name": "A" }
+        });
+        fs::write(&user, base.to_string()).unwrap();
+        let entries = json!([
+            { "id": "command:a", "name": "First" },
+            { "id": "command:new", "icon": "new-icon" },
+            { "id": "command:a", "name": "Second", "category": "Edit" },
+            "not an entry",
+            { "icon": "no id" }
+        ]);
+        fs::write(&overlay, entries.to_string()).unwrap();
+
+        let merged = merge_commands(&user, &overlay).unwrap();
+        // Base order first, then ids only the overlay has.
+        assert!(merged.keys().eq(["command:b", "command:a", "command:new"]));
+        assert_eq!(merged["command:b"], json!({ "icon": "b-icon" }));
+        // Later overlay entries win field by field; untouched fields stay.
+        assert_eq!(
+            merged["command:a"],
+            json!({ "icon": "a-icon", "name": "Second", "category": "Edit" })
+        );
+        assert_eq!(merged["command:new"], json!({ "icon": "new-icon" }));
+    }
+}
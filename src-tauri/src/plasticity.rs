@@ -0,0 +1,270 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::Manager;
+
+use crate::{
+    copy_mtime, fmt_path, io_err, library, menu, read_json_file, write_file_atomic, write_json_file,
+};
+
+// The only fields Plasticity reads from a radial menu file. Everything else
+// (keybinding overrides, colors, tags, notes) is builder-only metadata.
+const MENU_FIELDS: [&str; 3] = ["name", "command", "items"];
+const ITEM_FIELDS: [&str; 3] = ["command", "icon", "label"];
+
+// ---------- Conversion ----------
+/// Keeps only what Plasticity reads and drops empty slots, which Plasticity
+/// has no representation for.
+pub fn strip_to_plasticity(menu: &Value) -> Result<Value, String> {
+    let items = menu::items(menu)?
+        .iter()
+        .filter(|item| !menu::item_str(item, "command").is_empty())
+        .map(|item| pick(item, &ITEM_FIELDS))
+        .collect();
+    let mut out = pick(menu, &MENU_FIELDS);
+    if let Value::Object(obj) = &mut out {
+        obj.insert("items".into(), Value::Array(items));
+    }
+    Ok(out)
+}
+
+fn pick(value: &Value, fields: &[&str]) -> Value {
+    let mut out = Map::new();
+    for field in fields {
+        if let Some(v) = value.get(*field) {
+            out.insert(field.to_string(), v.clone());
+        }
+    }
+    Value::Object(out)
+}
+
+/// Fills in the fields the builder expects but a hand-written Plasticity
+/// file may leave out.
+pub fn from_plasticity(value: Value) -> Result<Value, String> {
+    let Value::Object(mut obj) = value else {
+        return Err(io_err("Plasticity menu must be a JSON object"));
+    };
+    obj.entry("name")
+        .or_insert_with(|| Value::String(String::new()));
+    obj.entry("command")
+        .or_insert_with(|| Value::String("default-menu".into()));
+    obj.entry("items").or_insert_with(|| Value::Array(vec![]));
+    let menu = Value::Object(obj);
+    menu::validate_menu(&menu).map_err(menu::validation_error)?;
+    Ok(menu)
+}
+
+// ---------- Round trip ----------
+#[derive(Serialize)]
+pub struct RoundtripReport {
+    pub lossless: bool,
+    /// One entry per field or slot that didn't survive, e.g. `slot 2: color`.
+    pub lost: Vec<String>,
+}
+
+/// What comes back when `exported` is written out and read in again with
+/// import_from_plasticity, compared against the builder's `original`.
+fn roundtrip(original: &Value, exported: &Value) -> Result<RoundtripReport, String> {
+    let text = serde_json::to_string(exported)
+        .map_err(|e| io_err(format!("serialize json failed: {e}")))?;
+    let reparsed: Value =
+        serde_json::from_str(&text).map_err(|e| io_err(format!("re-read export failed: {e}")))?;
+    let back = from_plasticity(reparsed)?;
+
+    let mut lost = vec![];
+    if let Some(fields) = original.as_object() {
+        for (key, value) in fields {
+            if key != "items" && back.get(key) != Some(value) {
+                lost.push(key.clone());
+            }
+        }
+    }
+    let (old, new) = (menu::items(original)?, menu::items(&back)?);
+    // Exporters either keep every slot or drop the empty ones; pair slots
+    // up accordingly.
+    let keeps_empty = old.len() == new.len();
+    let mut next = new.iter();
+    for (i, item) in old.iter().enumerate() {
+        if !keeps_empty && menu::is_empty_slot(item) {
+            lost.push(format!("slot {i}: empty slot"));
+            continue;
+        }
+        let Some(returned) = next.next() else {
+            lost.push(format!("slot {i}"));
+            continue;
+        };
+        if let Some(fields) = item.as_object() {
+            for (key, value) in fields {
+                if returned.get(key) != Some(value) {
+                    lost.push(format!("slot {i}: {key}"));
+                }
+            }
+        }
+    }
+    Ok(RoundtripReport {
+        lossless: lost.is_empty(),
+        lost,
+    })
+}
+
+/// Runs the menu through the Plasticity exporter and importer in memory and
+/// lists whatever doesn't come back.
+#[tauri::command]
+pub fn verify_plasticity_roundtrip(menu: Value) -> Result<RoundtripReport, String> {
+    menu::validate_menu(&menu).map_err(menu::validation_error)?;
+    roundtrip(&menu, &strip_to_plasticity(&menu)?)
+}
+
+// ---------- Commands ----------
+/// The builder menu is written as is, or stripped to what Plasticity reads
+/// when minified. Written as is it's still a builder file, so builder
+/// metadata such as `schema_version` stays: Plasticity ignores it, and it
+/// tells a later build which migrations the file has had. With `verify` the written layout is round-tripped and the
+/// report returned, so the UI can warn about anything the export drops.
+#[tauri::command]
+pub fn export_to_plasticity(
+    menu: Value,
+    path: String,
+    minify: Option<bool>,
+    verify: Option<bool>,
+) -> Result<Option<RoundtripReport>, String> {
+    menu::validate_menu(&menu).map_err(menu::validation_error)?;
+    let path = Path::new(&path);
+    let out = if minify.unwrap_or(false) {
+        strip_to_plasticity(&menu)?
+    } else {
+        menu.clone()
+    };
+    let report = if verify.unwrap_or(false) {
+        let report = roundtrip(&menu, &out)?;
+        if !report.lossless {
+            tracing::warn!(
+                "export to {} loses {}",
+                fmt_path(path),
+                report.lost.join(", ")
+            );
+        }
+        Some(report)
+    } else {
+        None
+    };
+    if minify.unwrap_or(false) {
+        let compact = serde_json::to_string(&out)
+            .map_err(|e| io_err(format!("serialize json failed: {e}")))?;
+        write_file_atomic(path, compact.as_bytes())?;
+    } else {
+        write_json_file(path, &out)?;
+    }
+    Ok(report)
+}
+
+/// Writes every valid menu in the library to `out_directory` in the layout
+/// Plasticity reads, keeping relative paths. With `preserve_mtime` each
+/// output gets its source's modified time, so mtime-ordered pipelines see
+/// the same order as the library. Menus that don't parse or validate are
+/// logged and skipped. Returns the relative paths written.
+#[tauri::command]
+pub fn export_all_to_plasticity(
+    directory: String,
+    out_directory: String,
+    preserve_mtime: Option<bool>,
+) -> Result<Vec<String>, String> {
+    let dir = Path::new(&directory);
+    let out_dir = Path::new(&out_directory);
+    let mut written = vec![];
+    for entry in library::load_library(dir)? {
+        let Ok(value) = entry.parsed else {
+            continue;
+        };
+        if let Err(errors) = menu::validate_menu(&value) {
+            tracing::warn!("skipping {}: {}", entry.rel, errors.join("; "));
+            continue;
+        }
+        let target = library::resolve_relative(out_dir, &entry.rel)?;
+        write_json_file(&target, &strip_to_plasticity(&value)?)?;
+        if preserve_mtime.unwrap_or(false) {
+            copy_mtime(&dir.join(&entry.rel), &target)?;
+        }
+        written.push(entry.rel);
+    }
+    Ok(written)
+}
+
+#[tauri::command]
+pub fn import_from_plasticity(path: String) -> Result<Value, String> {
+    from_plasticity(read_json_file(Path::new(&path))?)
+}
+
+// ---------- Locations ----------
+const RADIALS_SUBDIR: &str = "radials";
+
+/// The first existing folder among the places Plasticity keeps its settings:
+/// `~/.plasticity`, then `Plasticity` under the OS config directory.
+pub fn plasticity_config_dir(app: &tauri::AppHandle) -> Option<PathBuf> {
+    let paths = app.path();
+    let candidates = [
+        paths.home_dir().ok().map(|h| h.join(".plasticity")),
+        paths.config_dir().ok().map(|c| c.join("Plasticity")),
+    ];
+    candidates.into_iter().flatten().find(|p| p.is_dir())
+}
+
+#[tauri::command]
+pub fn find_plasticity_config_dir(app: tauri::AppHandle) -> Option<String> {
+    plasticity_config_dir(&app).map(|p| fmt_path(&p))
+}
+
+/// A suggestion for first-run setup; nothing is created.
+#[tauri::command]
+pub fn default_radials_directory(app: tauri::AppHandle) -> Result<String, String> {
+    if let Some(config) = plasticity_config_dir(&app) {
+        return Ok(fmt_path(&config.join(RADIALS_SUBDIR)));
+    }
+    let docs = app
+        .path()
+        .document_dir()
+        .map_err(|e| io_err(format!("document_dir error: {e}")))?;
+    Ok(fmt_path(&docs.join("Radials")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn minified_export_reimports_to_the_same_menu() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("menu.json");
+        let menu = json!({
+            "name": "Modeling",
+            "command": "default-menu:modeling",
+            "items": [
+                { "command": "command:extrude", "icon": "extrude.svg", "label": "Extrude" },
+                { "command": "", "icon": "", "label": "" },
+                { "command": "view:radial:default-menu:select", "icon": "", "label": "Select", "color": "#ff0000" }
+            ],
+            "notes": "builder only"
+        });
+
+        export_to_plasticity(menu.clone(), fmt_path(&path), Some(true), None).unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains('\n'));
+        let back = import_from_plasticity(fmt_path(&path)).unwrap();
+
+        // Only what Plasticity reads comes back, minus the empty slot.
+        let expected = json!({
+            "name": "Modeling",
+            "command": "default-menu:modeling",
+            "items": [
+                { "command": "command:extrude", "icon": "extrude.svg", "label": "Extrude" },
+                { "command": "view:radial:default-menu:select", "icon": "", "label": "Select" }
+            ]
+        });
+        assert_eq!(back, expected);
+        assert_eq!(back, strip_to_plasticity(&menu).unwrap());
+    }
+}
@@ -0,0 +1,154 @@
+use std::{collections::BTreeMap, path::PathBuf, sync::RwLock};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{app_data_dir, io_err, menu, read_json_file, write_json_file};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IndentStyle {
+    Spaces,
+    Tabs,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    /// CRLF on Windows, LF elsewhere.
+    Native,
+}
+
+/// User preferences persisted in app_data_dir/preferences.json. Missing
+/// fields fall back to their defaults so older files keep loading.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Preferences {
+    pub indent_style: IndentStyle,
+    /// Spaces per level; ignored for tabs.
+    pub indent_width: u8,
+    pub line_ending: LineEnding,
+    /// Global accelerator (as normalized by the shortcut parser) to the menu
+    /// file it opens; see hotkeys.rs.
+    pub menu_hotkeys: BTreeMap<String, String>,
+    /// Drop folder watched for new menus; see watch.rs.
+    pub inbox_directory: Option<String>,
+    /// Copy valid, new inbox menus into the radials directory on arrival
+    /// instead of only announcing them.
+    pub inbox_auto_import: bool,
+    /// Most menus a directory should hold; save_radial_menu warns when a new
+    /// file goes past it. None means no limit.
+    pub menu_count_limit: Option<usize>,
+    /// Backups kept per menu; the oldest beyond this are pruned on save.
+    pub history_limit: usize,
+    /// Longest slice label, in grapheme clusters, before
+    /// find_overlong_labels flags it.
+    pub label_limit: usize,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Preferences {
+            indent_style: IndentStyle::Spaces,
+            indent_width: 2,
+            line_ending: LineEnding::Lf,
+            menu_hotkeys: BTreeMap::new(),
+            inbox_directory: None,
+            inbox_auto_import: false,
+            menu_count_limit: None,
+            history_limit: 20,
+            label_limit: menu::DEFAULT_LABEL_LIMIT,
+        }
+    }
+}
+
+// write_json_file has no AppHandle (and shouldn't need one), so the active
+// preferences are mirrored here whenever they're loaded or saved.
+static ACTIVE: RwLock<Option<Preferences>> = RwLock::new(None);
+
+pub fn active() -> Preferences {
+    ACTIVE
+        .read()
+        .ok()
+        .and_then(|p| p.clone())
+        .unwrap_or_default()
+}
+
+fn set_active(prefs: &Preferences) {
+    if let Ok(mut slot) = ACTIVE.write() {
+        *slot = Some(prefs.clone());
+    }
+}
+
+// ---------- Persistence ----------
+fn preferences_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_data_dir(app)?.join("preferences.json"))
+}
+
+pub fn load(app: &tauri::AppHandle) -> Result<Preferences, String> {
+    let path = preferences_path(app)?;
+    let prefs = if path.exists() {
+        serde_json::from_value(read_json_file(&path)?)
+            .map_err(|e| io_err(format!("invalid preferences: {e}")))?
+    } else {
+        Preferences::default()
+    };
+    set_active(&prefs);
+    Ok(prefs)
+}
+
+#[tauri::command]
+pub fn get_preferences(app: tauri::AppHandle) -> Result<Preferences, String> {
+    load(&app)
+}
+
+#[tauri::command]
+pub fn set_preferences(preferences: Preferences, app: tauri::AppHandle) -> Result<(), String> {
+    if preferences.indent_style == IndentStyle::Spaces && preferences.indent_width > 8 {
+        return Err(io_err("indent_width must be at most 8"));
+    }
+    save(&app, &preferences)
+}
+
+pub fn save(app: &tauri::AppHandle, preferences: &Preferences) -> Result<(), String> {
+    // Switch first so the preferences file itself uses the new style.
+    set_active(preferences);
+    let value = serde_json::to_value(preferences)
+        .map_err(|e| io_err(format!("serialize preferences failed: {e}")))?;
+    write_json_file(&preferences_path(app)?, &value)
+}
+
+// ---------- Formatting ----------
+/// Pretty-prints with the active indentation preference.
+pub fn to_json_string(value: &Value) -> Result<String, String> {
+    let prefs = active();
+    let indent = match prefs.indent_style {
+        IndentStyle::Tabs => "\t".to_string(),
+        IndentStyle::Spaces => " ".repeat(usize::from(prefs.indent_width)),
+    };
+    let mut out = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut ser = serde_json::Serializer::with_formatter(&mut out, formatter);
+    value
+        .serialize(&mut ser)
+        .map_err(|e| io_err(format!("serialize json failed: {e}")))?;
+    String::from_utf8(out).map_err(|e| io_err(format!("serialize json failed: {e}")))
+}
+
+/// Converts to the preferred line endings. Pretty JSON only ever contains
+/// `\n` between tokens (string contents are escaped), so a plain replace is
+/// safe.
+pub fn with_line_endings(text: String) -> String {
+    let crlf = match active().line_ending {
+        LineEnding::Lf => false,
+        LineEnding::Crlf => true,
+        LineEnding::Native => cfg!(windows),
+    };
+    if crlf {
+        text.replace('\n', "\r\n")
+    } else {
+        text
+    }
+}
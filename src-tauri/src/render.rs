@@ -0,0 +1,410 @@
+use std::{f64::consts::PI, fmt::Write, path::Path};
+
+use printpdf::{ColorBits, ColorSpace, Image, ImageTransform, ImageXObject, Mm, PdfDocument, Px};
+use resvg::{tiny_skia, usvg};
+use serde::Serialize;
+use serde_json::Value;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{io_err, keys, menu, palette, write_file_atomic};
+
+// Labels always render with this font so previews look the same on every
+// machine, whatever the system has installed.
+const FONT: &[u8] = include_bytes!("../resources/fonts/DejaVuSans.ttf");
+const FONT_FAMILY: &str = "DejaVu Sans";
+
+const VIEW: f64 = 512.0;
+const OUTER_R: f64 = 240.0;
+const INNER_R: f64 = 84.0;
+const LABEL_FONT_SIZE: f64 = 18.0;
+const SLOT_FILL: &str = "#2b2f36";
+const EMPTY_FILL: &str = "#1b1e23";
+const STROKE: &str = "#0e1013";
+const TEXT_FILL: &str = "#e8eaed";
+
+/// Largest PNG edge we'll allocate; 8192² RGBA is already 256 MiB.
+const MAX_PNG_SIZE: u32 = 8192;
+
+// ---------- SVG ----------
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+fn point(r: f64, angle: f64) -> (f64, f64) {
+    let c = VIEW / 2.0;
+    (c + r * angle.cos(), c + r * angle.sin())
+}
+
+fn wedge_path(start: f64, end: f64) -> String {
+    let large = i32::from(end - start > PI);
+    let (ox1, oy1) = point(OUTER_R, start);
+    let (ox2, oy2) = point(OUTER_R, end);
+    let (ix1, iy1) = point(INNER_R, end);
+    let (ix2, iy2) = point(INNER_R, start);
+    format!(
+        "M{ox1:.2} {oy1:.2}A{OUTER_R} {OUTER_R} 0 {large} 1 {ox2:.2} {oy2:.2}\
+         L{ix1:.2} {iy1:.2}A{INNER_R} {INNER_R} 0 {large} 0 {ix2:.2} {iy2:.2}Z"
+    )
+}
+
+// A single slot is the whole ring, which one arc can't describe.
+fn ring_path() -> String {
+    let c = VIEW / 2.0;
+    format!(
+        "M{} {c}A{OUTER_R} {OUTER_R} 0 1 0 {} {c}A{OUTER_R} {OUTER_R} 0 1 0 {} {c}Z\
+         M{} {c}A{INNER_R} {INNER_R} 0 1 1 {} {c}A{INNER_R} {INNER_R} 0 1 1 {} {c}Z",
+        c - OUTER_R,
+        c + OUTER_R,
+        c - OUTER_R,
+        c - INNER_R,
+        c + INNER_R,
+        c - INNER_R,
+    )
+}
+
+/// The menu drawn as its ring: slot 0 at the top, going clockwise, each
+/// slot labelled and tinted with its `color` when it has one.
+pub fn menu_svg(menu_value: &Value) -> Result<String, String> {
+    ring_svg(menu_value, None)
+}
+
+// With `tips`, each slot is wrapped in a `<g class="slice">` carrying its
+// index and a `<title>`, so browsers show a tooltip and scripts can hook it.
+fn ring_svg(menu_value: &Value, tips: Option<&[String]>) -> Result<String, String> {
+    let items = menu::items(menu_value)?;
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{VIEW}" height="{VIEW}" viewBox="0 0 {VIEW} {VIEW}" font-family="{FONT_FAMILY}">"#
+    );
+    let step = 2.0 * PI / items.len().max(1) as f64;
+    for (i, item) in items.iter().enumerate() {
+        let mid = -PI / 2.0 + step * i as f64;
+        let fill = match item.get("color").and_then(Value::as_str) {
+            Some(color) => color,
+            None if menu::is_empty_slot(item) => EMPTY_FILL,
+            None => SLOT_FILL,
+        };
+        let d = if items.len() == 1 {
+            ring_path()
+        } else {
+            wedge_path(mid - step / 2.0, mid + step / 2.0)
+        };
+        if let Some(tips) = tips {
+            let tip = tips.get(i).map(String::as_str).unwrap_or("");
+            let _ = write!(
+                svg,
+                r#"<g class="slice" data-slot="{i}"><title>{}</title>"#,
+                escape(tip)
+            );
+        }
+        let _ = write!(
+            svg,
+            r#"<path d="{d}" fill="{}" fill-rule="evenodd" stroke="{STROKE}" stroke-width="3"/>"#,
+            escape(fill)
+        );
+        let label = menu::item_str(item, "label");
+        if !label.is_empty() {
+            let (x, y) = point((OUTER_R + INNER_R) / 2.0, mid);
+            let _ = write!(
+                svg,
+                r#"<text x="{x:.2}" y="{y:.2}" dy="0.35em" text-anchor="middle" font-size="{LABEL_FONT_SIZE}" fill="{TEXT_FILL}">{}</text>"#,
+                escape(label)
+            );
+        }
+        if tips.is_some() {
+            svg.push_str("</g>");
+        }
+    }
+    let c = VIEW / 2.0;
+    let name = menu::item_str(menu_value, "name");
+    let _ = write!(
+        svg,
+        r#"<circle cx="{c}" cy="{c}" r="{}" fill="{EMPTY_FILL}" stroke="{STROKE}" stroke-width="3"/><text x="{c}" y="{c}" dy="0.35em" text-anchor="middle" font-size="20" fill="{TEXT_FILL}">{}</text></svg>"#,
+        INNER_R - 6.0,
+        escape(name)
+    );
+    Ok(svg)
+}
+
+// ---------- Layout estimates ----------
+// DejaVu Sans averages a little over half an em per character.
+const AVG_CHAR_EM: f64 = 0.55;
+
+#[derive(Serialize)]
+pub struct SliceEstimate {
+    pub index: usize,
+    pub label: String,
+    /// Estimated label width over the room the slot has at label radius;
+    /// above 1.0 the label runs into its neighbours.
+    pub crowding: f64,
+    pub truncated: bool,
+}
+
+#[derive(Serialize)]
+pub struct LayoutEstimate {
+    pub ring_size: u32,
+    pub arc_degrees: f64,
+    pub slices: Vec<SliceEstimate>,
+    /// Filled slots past the end of a ring this size.
+    pub dropped: usize,
+}
+
+/// How the menu's labels would fit on rings of each requested size, in the
+/// order given. Widths are estimated from grapheme counts using the preview
+/// geometry above, so treat the scores as relative.
+#[tauri::command]
+pub fn estimate_layout(menu: Value, ring_sizes: Vec<u32>) -> Result<Vec<LayoutEstimate>, String> {
+    let items = menu::items(&menu)?;
+    let mid_r = (OUTER_R + INNER_R) / 2.0;
+    let mut out = vec![];
+    for ring_size in ring_sizes {
+        let n = ring_size as usize;
+        if !(menu::MIN_SLOTS..=menu::MAX_SLOTS).contains(&n) {
+            return Err(io_err(format!(
+                "ring size {ring_size} is outside {}..={}",
+                menu::MIN_SLOTS,
+                menu::MAX_SLOTS
+            )));
+        }
+        let step = 2.0 * PI / n as f64;
+        // The chord across the slot at label radius, never wider than the band.
+        let room = (2.0 * mid_r * (step / 2.0).sin()).min(2.0 * (OUTER_R - INNER_R));
+        let slices = items
+            .iter()
+            .take(n)
+            .enumerate()
+            .map(|(index, item)| {
+                let label = menu::item_str(item, "label");
+                let width = label.graphemes(true).count() as f64 * AVG_CHAR_EM * LABEL_FONT_SIZE;
+                let crowding = width / room;
+                SliceEstimate {
+                    index,
+                    label: label.to_string(),
+                    crowding,
+                    truncated: crowding > 1.0,
+                }
+            })
+            .collect();
+        let dropped = items
+            .iter()
+            .skip(n)
+            .filter(|item| !menu::is_empty_slot(item))
+            .count();
+        out.push(LayoutEstimate {
+            ring_size,
+            arc_degrees: 360.0 / n as f64,
+            slices,
+            dropped,
+        });
+    }
+    Ok(out)
+}
+
+// ---------- Export ----------
+#[tauri::command]
+pub fn export_menu_preview_svg(menu: Value, out_path: String) -> Result<(), String> {
+    menu::validate_menu(&menu).map_err(menu::validation_error)?;
+    let svg = menu_svg(&menu)?;
+    write_file_atomic(Path::new(&out_path), svg.as_bytes())
+}
+
+fn rasterize(svg: &str, size: u32) -> Result<tiny_skia::Pixmap, String> {
+    let mut options = usvg::Options {
+        font_family: FONT_FAMILY.to_string(),
+        ..usvg::Options::default()
+    };
+    options.fontdb_mut().load_font_data(FONT.to_vec());
+    let tree = usvg::Tree::from_str(svg, &options)
+        .map_err(|e| io_err(format!("render preview failed: {e}")))?;
+
+    let mut pixmap = tiny_skia::Pixmap::new(size, size)
+        .ok_or_else(|| io_err(format!("cannot allocate a {size}x{size} image")))?;
+    let scale = size as f32 / tree.size().width();
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    Ok(pixmap)
+}
+
+/// Rasterizes the SVG preview to a `size`×`size` PNG.
+#[tauri::command]
+pub fn export_menu_preview_png(menu: Value, out_path: String, size: u32) -> Result<(), String> {
+    if size == 0 || size > MAX_PNG_SIZE {
+        return Err(io_err(format!(
+            "size must be between 1 and {MAX_PNG_SIZE} pixels"
+        )));
+    }
+    menu::validate_menu(&menu).map_err(menu::validation_error)?;
+    let pixmap = rasterize(&menu_svg(&menu)?, size)?;
+    let png = pixmap
+        .encode_png()
+        .map_err(|e| io_err(format!("encode png failed: {e}")))?;
+    write_file_atomic(Path::new(&out_path), &png)
+}
+
+// ---------- HTML ----------
+const HTML_STYLE: &str = "body{margin:0;min-height:100vh;display:flex;flex-direction:column;align-items:center;justify-content:center;gap:16px;background:#15171b;color:#e8eaed;font-family:'DejaVu Sans',sans-serif}\
+svg{width:min(90vw,512px);height:auto}\
+.slice{cursor:pointer}.slice:hover path,.slice.active path{filter:brightness(1.45)}\
+#details{min-height:4.5em;text-align:center}#details>*{display:block}#details code{color:#9ecbff}";
+
+// Fills #details with the slot under the pointer; clicking pins it.
+const HTML_SCRIPT: &str = "const slots=JSON.parse(document.getElementById('slots').textContent);\
+const details=document.getElementById('details');let pinned=null;\
+function show(i){const s=slots[i];details.replaceChildren();if(!s)return;\
+const add=(tag,text)=>{const el=document.createElement(tag);el.textContent=text;details.appendChild(el);};\
+if(!s.command){add('div','(empty slot)');return;}\
+add('strong',s.label);add('code',s.command);\
+add('div',s.key?'Key: '+s.key:'No keybinding');}\
+document.querySelectorAll('.slice').forEach(g=>{const i=+g.dataset.slot;\
+g.addEventListener('mouseenter',()=>{if(pinned===null)show(i);});\
+g.addEventListener('mouseleave',()=>{if(pinned===null)show(-1);});\
+g.addEventListener('click',()=>{document.querySelectorAll('.slice.active').forEach(a=>a.classList.remove('active'));\
+if(pinned===i){pinned=null;}else{pinned=i;g.classList.add('active');}show(i);});});";
+
+/// A single HTML file with the ring inlined; hovering a slice shows its
+/// command and effective keybinding, clicking pins it. Everything is
+/// inlined, so the file opens in any browser with nothing else installed.
+#[tauri::command]
+pub fn export_menu_html(
+    menu: Value,
+    out_path: String,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    menu::validate_menu(&menu).map_err(menu::validation_error)?;
+    let commands = palette::merged_commands(&app)?;
+    let slots = keys::effective_keys(&menu, &commands)?;
+    let tips: Vec<String> = slots
+        .iter()
+        .map(|slot| match &slot.key {
+            _ if slot.command.is_empty() => "(empty slot)".to_string(),
+            Some(key) => format!("{}\n{}\nKey: {key}", slot.label, slot.command),
+            None => format!("{}\n{}", slot.label, slot.command),
+        })
+        .collect();
+    let svg = ring_svg(&menu, Some(&tips))?;
+    // `</` can't appear inside the script element, so it's escaped in the
+    // data; JSON.parse reads `<\/` back as `</`.
+    let data = serde_json::to_string(&slots)
+        .map_err(|e| io_err(format!("serialize slots failed: {e}")))?
+        .replace("</", "<\\/");
+
+    let name = menu::item_str(&menu, "name");
+    let title = if name.is_empty() { "Radial menu" } else { name };
+    let html = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title><style>{HTML_STYLE}</style></head><body>\n\
+         {svg}\n<div id=\"details\">Hover a slice to see its command.</div>\n\
+         <script type=\"application/json\" id=\"slots\">{data}</script>\n<script>{HTML_SCRIPT}</script>\n</body></html>\n",
+        escape(title)
+    );
+    write_file_atomic(Path::new(&out_path), html.as_bytes())
+}
+
+// ---------- PDF ----------
+// A4 portrait: the ring at the top, the slot table under it.
+const PAGE_W: f32 = 210.0;
+const PAGE_H: f32 = 297.0;
+const PDF_RING_MM: f32 = 130.0;
+const PDF_RING_PX: u32 = 1536;
+const PDF_FONT_SIZE: f32 = 10.0;
+const PDF_ROW_MM: f32 = 8.0;
+// (x in mm, width in characters) for slot, label, command and key.
+const PDF_COLUMNS: [(f32, usize); 4] = [(20.0, 4), (32.0, 28), (92.0, 34), (160.0, 18)];
+
+fn clip(text: &str, max: usize) -> String {
+    let graphemes: Vec<&str> = text.graphemes(true).collect();
+    if graphemes.len() <= max {
+        text.to_string()
+    } else {
+        format!("{}…", graphemes[..max - 1].concat())
+    }
+}
+
+// The PDF image has no alpha, so flatten the premultiplied pixels onto
+// white paper.
+fn rgb_on_white(pixmap: &tiny_skia::Pixmap) -> Vec<u8> {
+    let mut rgb = Vec::with_capacity(pixmap.data().len() / 4 * 3);
+    for px in pixmap.data().chunks_exact(4) {
+        let paper = 255 - px[3];
+        rgb.extend([px[0] + paper, px[1] + paper, px[2] + paper]);
+    }
+    rgb
+}
+
+/// A printable single-page cheat sheet: the ring preview plus each slot's
+/// label, command and effective key. Empty slots are listed as such so the
+/// slot numbers still line up with the ring; long text is cut with an
+/// ellipsis.
+#[tauri::command]
+pub fn export_menu_pdf(menu: Value, out_path: String, app: tauri::AppHandle) -> Result<(), String> {
+    menu::validate_menu(&menu).map_err(menu::validation_error)?;
+    let commands = palette::merged_commands(&app)?;
+    let slots = keys::effective_keys(&menu, &commands)?;
+    let pdf_err = |e: printpdf::Error| io_err(format!("write pdf failed: {e}"));
+
+    let name = menu::item_str(&menu, "name");
+    let title = if name.is_empty() { "Radial menu" } else { name };
+    let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_W), Mm(PAGE_H), "Cheat sheet");
+    let layer = doc.get_page(page).get_layer(layer);
+    let font = doc.add_external_font(FONT).map_err(pdf_err)?;
+
+    let mut y = PAGE_H - 20.0;
+    layer.use_text(clip(title, 60), 18.0, Mm(20.0), Mm(y), &font);
+
+    let pixmap = rasterize(&menu_svg(&menu)?, PDF_RING_PX)?;
+    let image = Image::from(ImageXObject {
+        width: Px(PDF_RING_PX as usize),
+        height: Px(PDF_RING_PX as usize),
+        color_space: ColorSpace::Rgb,
+        bits_per_component: ColorBits::Bit8,
+        interpolate: true,
+        image_data: rgb_on_white(&pixmap),
+        image_filter: None,
+        smask: None,
+        clipping_bbox: None,
+    });
+    y -= 8.0 + PDF_RING_MM;
+    image.add_to_layer(
+        layer.clone(),
+        ImageTransform {
+            translate_x: Some(Mm((PAGE_W - PDF_RING_MM) / 2.0)),
+            translate_y: Some(Mm(y)),
+            dpi: Some(PDF_RING_PX as f32 / (PDF_RING_MM / 25.4)),
+            ..ImageTransform::default()
+        },
+    );
+
+    y -= 12.0;
+    let row = |cells: [&str; 4], y: f32| {
+        for ((x, width), cell) in PDF_COLUMNS.iter().zip(cells) {
+            layer.use_text(clip(cell, *width), PDF_FONT_SIZE, Mm(*x), Mm(y), &font);
+        }
+    };
+    row(["Slot", "Label", "Command", "Key"], y);
+    for slot in &slots {
+        y -= PDF_ROW_MM;
+        let index = slot.index.to_string();
+        if slot.command.is_empty() {
+            row([&index, "(empty)", "", ""], y);
+        } else {
+            let key = slot.key.as_deref().unwrap_or("");
+            row([&index, &slot.label, &slot.command, key], y);
+        }
+    }
+
+    let bytes = doc.save_to_bytes().map_err(pdf_err)?;
+    write_file_atomic(Path::new(&out_path), &bytes)
+}
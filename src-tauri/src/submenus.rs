@@ -0,0 +1,234 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{fmt_path, library, menu, palette, read_json_file};
+
+// ---------- Reference graph ----------
+/// The menu command a slot opens as a submenu, if it opens one: slots link
+/// to other menus as `view:radial:<menu command>`.
+pub fn submenu_target(item: &Value) -> Option<&str> {
+    menu::item_str(item, "command").strip_prefix(palette::NESTED_MENU_PREFIX)
+}
+
+/// The library's parseable menus by relative path, plus which file each
+/// menu command belongs to. When two files share a command the first in
+/// path order wins, matching what a sorted load does.
+pub struct MenuIndex {
+    pub menus: BTreeMap<String, Value>,
+    pub by_command: HashMap<String, String>,
+}
+
+pub fn index_library(dir: &Path) -> Result<MenuIndex, String> {
+    let mut menus = BTreeMap::new();
+    let mut by_command = HashMap::new();
+    for entry in library::load_library(dir)? {
+        let Ok(value) = entry.parsed else {
+            continue;
+        };
+        let command = menu::item_str(&value, "command");
+        if !command.is_empty() {
+            by_command
+                .entry(command.to_string())
+                .or_insert_with(|| entry.rel.clone());
+        }
+        menus.insert(entry.rel, value);
+    }
+    Ok(MenuIndex { menus, by_command })
+}
+
+// ---------- Cycles ----------
+#[derive(Serialize)]
+pub struct BrokenRef {
+    pub file: String,
+    pub index: usize,
+    /// The menu command nothing in the library has.
+    pub target: String,
+}
+
+#[derive(Serialize)]
+pub struct SubmenuCycles {
+    pub cycles: Vec<Vec<String>>,
+    pub broken: Vec<BrokenRef>,
+}
+
+// Tarjan's strongly connected components over file indexes.
+struct Tarjan<'a> {
+    edges: &'a [Vec<usize>],
+    next: usize,
+    order: Vec<Option<usize>>,
+    low: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, v: usize) {
+        self.order[v] = Some(self.next);
+        self.low[v] = self.next;
+        self.next += 1;
+        self.stack.push(v);
+        self.on_stack[v] = true;
+        for &w in &self.edges[v] {
+            match self.order[w] {
+                None => {
+                    self.visit(w);
+                    self.low[v] = self.low[v].min(self.low[w]);
+                }
+                Some(order) if self.on_stack[w] => self.low[v] = self.low[v].min(order),
+                Some(_) => {}
+            }
+        }
+        if Some(self.low[v]) == self.order[v] {
+            let mut component = vec![];
+            while let Some(w) = self.stack.pop() {
+                self.on_stack[w] = false;
+                component.push(w);
+                if w == v {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+/// Groups of menus that reach each other through submenu links, which
+/// Plasticity would follow forever; each group lists its files in path
+/// order. Links to menu commands no file has are reported as broken.
+#[tauri::command]
+pub fn find_submenu_cycles(directory: String) -> Result<SubmenuCycles, String> {
+    let index = index_library(Path::new(&directory))?;
+    let files: Vec<&String> = index.menus.keys().collect();
+    let position: HashMap<&str, usize> = files
+        .iter()
+        .enumerate()
+        .map(|(i, rel)| (rel.as_str(), i))
+        .collect();
+
+    let mut edges = vec![vec![]; files.len()];
+    let mut broken = vec![];
+    for (from, (rel, value)) in index.menus.iter().enumerate() {
+        let Ok(items) = menu::items(value) else {
+            continue;
+        };
+        for (slot, item) in items.iter().enumerate() {
+            let Some(target) = submenu_target(item) else {
+                continue;
+            };
+            match index.by_command.get(target) {
+                Some(to) => edges[from].push(position[to.as_str()]),
+                None => broken.push(BrokenRef {
+                    file: rel.clone(),
+                    index: slot,
+                    target: target.to_string(),
+                }),
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        edges: &edges,
+        next: 0,
+        order: vec![None; files.len()],
+        low: vec![0; files.len()],
+        stack: vec![],
+        on_stack: vec![false; files.len()],
+        components: vec![],
+    };
+    for v in 0..files.len() {
+        if tarjan.order[v].is_none() {
+            tarjan.visit(v);
+        }
+    }
+    let mut cycles: Vec<Vec<String>> = tarjan
+        .components
+        .into_iter()
+        .filter(|c| c.len() > 1 || edges[c[0]].contains(&c[0]))
+        .map(|mut c| {
+            c.sort_unstable();
+            c.into_iter().map(|i| files[i].clone()).collect()
+        })
+        .collect();
+    cycles.sort();
+    Ok(SubmenuCycles { cycles, broken })
+}
+
+// ---------- Tree ----------
+/// How deep resolve_submenu_tree follows links; well past anything a
+/// person navigates, and a hard stop even if cycle detection were fooled.
+const MAX_SUBMENU_DEPTH: usize = 8;
+
+fn resolve_node(
+    value: &Value,
+    file: &str,
+    index: &MenuIndex,
+    depth: usize,
+    path: &mut Vec<String>,
+) -> Value {
+    path.push(menu::item_str(value, "command").to_string());
+    let items: Vec<Value> = menu::items(value)
+        .map(|items| items.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .map(|item| {
+            let mut slot = item.clone();
+            if let (Some(target), Value::Object(obj)) = (submenu_target(item), &mut slot) {
+                obj.insert("submenu".into(), resolve_link(target, index, depth, path));
+            }
+            slot
+        })
+        .collect();
+    path.pop();
+    json!({
+        "file": file,
+        "name": menu::item_str(value, "name"),
+        "command": menu::item_str(value, "command"),
+        "items": items,
+    })
+}
+
+fn resolve_link(target: &str, index: &MenuIndex, depth: usize, path: &mut Vec<String>) -> Value {
+    let Some((file, value)) = index
+        .by_command
+        .get(target)
+        .and_then(|rel| Some((rel, index.menus.get(rel)?)))
+    else {
+        return json!({ "target": target, "status": "broken" });
+    };
+    if path.iter().any(|seen| seen == target) {
+        return json!({ "target": target, "status": "cycle", "file": file });
+    }
+    if depth + 1 >= MAX_SUBMENU_DEPTH {
+        return json!({ "target": target, "status": "depth_limit", "file": file });
+    }
+    json!({
+        "target": target,
+        "status": "resolved",
+        "menu": resolve_node(value, file, index, depth + 1, path),
+    })
+}
+
+/// The menu at `path` with each submenu slot carrying a `submenu` object:
+/// `status` "resolved" with the linked `menu` inlined (recursively), or
+/// "broken", "cycle" or "depth_limit" where following it stops. Links
+/// resolve against the menus in `directory`.
+#[tauri::command]
+pub fn resolve_submenu_tree(path: String, directory: String) -> Result<Value, String> {
+    let root_path = Path::new(&path);
+    let root = read_json_file(root_path)?;
+    menu::validate_menu(&root).map_err(menu::validation_error)?;
+    let index = index_library(Path::new(&directory))?;
+    Ok(resolve_node(
+        &root,
+        &fmt_path(root_path),
+        &index,
+        0,
+        &mut vec![],
+    ))
+}
@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+
+/// The removable or network volume `path` lives on, when that volume isn't
+/// currently mounted. None for paths on the system drive, on volumes that
+/// are mounted, or whose volume can't be told from the path.
+pub fn unmounted_volume(path: &Path) -> Option<PathBuf> {
+    let root = volume_root(path)?;
+    (!is_mounted(&root)).then_some(root)
+}
+
+// ---------- Windows ----------
+// `E:\` or `\\server\share\`; a drive letter only exists while its volume is
+// attached.
+#[cfg(windows)]
+fn volume_root(path: &Path) -> Option<PathBuf> {
+    match path.components().next()? {
+        std::path::Component::Prefix(prefix) => {
+            let mut root = PathBuf::from(prefix.as_os_str());
+            root.push("\\");
+            Some(root)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(windows)]
+fn is_mounted(root: &Path) -> bool {
+    root.exists()
+}
+
+// ---------- macOS ----------
+// Everything but the boot volume mounts at /Volumes/<name>, and the mount
+// point is removed when the volume goes away.
+#[cfg(target_os = "macos")]
+fn volume_root(path: &Path) -> Option<PathBuf> {
+    let name = path.strip_prefix("/Volumes").ok()?.components().next()?;
+    Some(Path::new("/Volumes").join(name))
+}
+
+#[cfg(target_os = "macos")]
+fn is_mounted(root: &Path) -> bool {
+    root.exists()
+}
+
+// ---------- Linux ----------
+// udisks mounts at /media/<user>/<label> or /run/media/<user>/<label>;
+// hand-made mounts usually sit directly under /mnt. Mount points there can
+// outlive the mount as empty folders, so check the mount table instead.
+#[cfg(not(any(windows, target_os = "macos")))]
+fn volume_root(path: &Path) -> Option<PathBuf> {
+    for (base, depth) in [("/run/media", 2), ("/media", 2), ("/mnt", 1)] {
+        let Ok(rest) = path.strip_prefix(base) else {
+            continue;
+        };
+        let parts: Vec<_> = rest.components().take(depth).collect();
+        if parts.len() == depth {
+            return Some(parts.iter().fold(PathBuf::from(base), |p, c| p.join(c)));
+        }
+    }
+    None
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn is_mounted(root: &Path) -> bool {
+    let Ok(mounts) = std::fs::read_to_string("/proc/self/mounts") else {
+        // No mount table to go on; don't claim the drive is gone.
+        return true;
+    };
+    mounts
+        .lines()
+        .filter_map(|line| line.split(' ').nth(1))
+        .any(|point| Path::new(&unescape_mount_point(point)) == root)
+}
+
+// The mount table writes spaces, tabs, newlines and backslashes as
+// three-digit octal escapes.
+#[cfg(not(any(windows, target_os = "macos")))]
+fn unescape_mount_point(point: &str) -> String {
+    let mut out = String::with_capacity(point.len());
+    let mut rest = point;
+    while let Some(i) = rest.find('\\') {
+        out.push_str(&rest[..i]);
+        let code = rest
+            .get(i + 1..i + 4)
+            .and_then(|oct| u8::from_str_radix(oct, 8).ok());
+        match code {
+            Some(byte) => {
+                out.push(char::from(byte));
+                rest = &rest[i + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,305 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::{mpsc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{Emitter, Manager};
+
+use crate::{
+    app_data_dir, fmt_path, io_err, library, menu, palette, prefs, saved_radials_directory,
+    write_file_atomic,
+};
+
+/// Watchers stay alive only as long as they're owned, so they live in
+/// managed state rather than on the stack of whoever started them.
+#[derive(Default)]
+pub struct Watchers {
+    commands: Mutex<Option<RecommendedWatcher>>,
+    inbox: Mutex<Option<RecommendedWatcher>>,
+    radials: Mutex<Option<RecommendedWatcher>>,
+}
+
+/// Quiet period before a burst of events is reported; one save from an
+/// editor is often a create, a few modifies and a rename.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+// ---------- Commands overlay ----------
+/// Re-checks the overlay whenever it changes on disk and tells the frontend
+/// whether it's currently usable, so a broken hand edit shows up right away
+/// instead of on the next reload.
+pub fn watch_commands_overlay(app: &tauri::AppHandle) -> Result<(), String> {
+    let dir = app_data_dir(app)?;
+    let overlay = palette::overlay_path(app)?;
+    let handle = app.clone();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        let Ok(event) = res else { return };
+        if event.paths.iter().any(|p| p == &overlay) {
+            emit_overlay_status(&handle, &overlay);
+        }
+    })
+    .map_err(|e| io_err(format!("create commands watcher failed: {e}")))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| io_err(format!("watch {} failed: {e}", fmt_path(&dir))))?;
+
+    let state = app.state::<Watchers>();
+    let mut slot = state
+        .commands
+        .lock()
+        .map_err(|_| io_err("watcher state poisoned"))?;
+    *slot = Some(watcher);
+    Ok(())
+}
+
+fn emit_overlay_status(app: &tauri::AppHandle, overlay: &Path) {
+    let result = match palette::check_overlay(overlay) {
+        Ok(()) => app.emit("commands-valid", ()),
+        Err(error) => app.emit("commands-invalid", error),
+    };
+    if let Err(e) = result {
+        tracing::warn!("emit commands status failed: {e}");
+    }
+}
+
+// ---------- Inbox ----------
+/// Emitted as `menu-inbox` for each valid menu that lands in the inbox.
+#[derive(Serialize, Clone)]
+pub struct InboxMenu {
+    pub path: String,
+    pub menu: Value,
+    /// Library menu with the same content, if there already is one.
+    pub duplicate_of: Option<String>,
+    /// Where it was copied to when inbox_auto_import is on.
+    pub imported_as: Option<String>,
+}
+
+/// Starts watching the inbox saved in preferences, if any. Called from
+/// setup and whenever the inbox changes; the previous watcher is dropped.
+pub fn watch_inbox(app: &tauri::AppHandle) -> Result<(), String> {
+    let state = app.state::<Watchers>();
+    let mut slot = state
+        .inbox
+        .lock()
+        .map_err(|_| io_err("watcher state poisoned"))?;
+    *slot = None;
+    let Some(dir) = prefs::active().inbox_directory else {
+        return Ok(());
+    };
+    let dir = PathBuf::from(dir);
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        let Ok(event) = res else { return };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            return;
+        }
+        for path in event.paths {
+            if path.extension().map(|x| x == "json").unwrap_or(false) {
+                let _ = tx.send(path);
+            }
+        }
+    })
+    .map_err(|e| io_err(format!("create inbox watcher failed: {e}")))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| io_err(format!("watch {} failed: {e}", fmt_path(&dir))))?;
+    let handle = app.clone();
+    thread::spawn(move || debounce_inbox(handle, rx));
+    *slot = Some(watcher);
+    Ok(())
+}
+
+// Like debounce_radials: each file is taken once per burst, after the
+// watcher has been quiet for DEBOUNCE, so one drop is one `menu-inbox`.
+fn debounce_inbox(app: tauri::AppHandle, rx: mpsc::Receiver<PathBuf>) {
+    let mut pending: BTreeSet<PathBuf> = BTreeSet::new();
+    loop {
+        match rx.recv_timeout(DEBOUNCE) {
+            Ok(path) => {
+                pending.insert(path);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                for path in std::mem::take(&mut pending) {
+                    take_inbox_menu(&app, &path);
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+// Files still being written fail to parse and are skipped; the write that
+// finishes them fires another event.
+fn take_inbox_menu(app: &tauri::AppHandle, path: &Path) {
+    let Ok(bytes) = fs::read(path) else { return };
+    let Ok(menu) = serde_json::from_slice::<Value>(&bytes) else {
+        return;
+    };
+    if menu::validate_menu(&menu).is_err() {
+        return;
+    }
+    let radials = saved_radials_directory(app).ok().flatten();
+    let duplicate_of = radials.as_deref().and_then(|dir| {
+        library::load_library(Path::new(dir))
+            .ok()?
+            .into_iter()
+            .find(|entry| entry.parsed.as_ref().ok() == Some(&menu))
+            .map(|entry| entry.rel)
+    });
+    let mut imported_as = None;
+    if let (Some(dir), None, true) = (&radials, &duplicate_of, prefs::active().inbox_auto_import) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let target = library::unique_menu_path(Path::new(dir), &name);
+        match write_file_atomic(&target, &bytes) {
+            Ok(()) => imported_as = Some(fmt_path(&target)),
+            Err(e) => tracing::warn!("inbox import of {} failed: {e}", fmt_path(path)),
+        }
+    }
+    let payload = InboxMenu {
+        path: fmt_path(path),
+        menu,
+        duplicate_of,
+        imported_as,
+    };
+    if let Err(e) = app.emit("menu-inbox", payload) {
+        tracing::warn!("emit menu-inbox failed: {e}");
+    }
+}
+
+/// Sets the drop folder another tool writes menus into and starts watching
+/// it. An empty `dir` turns the inbox off.
+#[tauri::command]
+pub fn set_inbox_directory(dir: String, app: tauri::AppHandle) -> Result<(), String> {
+    let dir = dir.trim();
+    if !dir.is_empty() && !Path::new(dir).is_dir() {
+        return Err(io_err(format!("directory {dir} does not exist")));
+    }
+    let mut preferences = prefs::load(&app)?;
+    preferences.inbox_directory = (!dir.is_empty()).then(|| dir.to_string());
+    prefs::save(&app, &preferences)?;
+    watch_inbox(&app)
+}
+
+// ---------- Radials directory ----------
+/// Emitted as `radials-changed`, once per file per burst.
+#[derive(Serialize, Clone)]
+pub struct RadialsChange {
+    /// Forward-slash path relative to the watched directory.
+    pub path: String,
+    /// "created", "modified" or "removed".
+    pub kind: &'static str,
+}
+
+// Menus only: `.json` files outside hidden folders, so backups, history
+// and temp files don't count.
+fn radials_rel(roots: &[PathBuf], path: &Path) -> Option<String> {
+    if path.extension().map(|x| x != "json").unwrap_or(true) {
+        return None;
+    }
+    let rel = roots.iter().find_map(|root| path.strip_prefix(root).ok())?;
+    let parts: Vec<String> = rel
+        .components()
+        .map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    if parts.iter().any(|part| part.starts_with('.')) {
+        return None;
+    }
+    Some(parts.join("/"))
+}
+
+fn change_kind(kind: &EventKind) -> Option<&'static str> {
+    match kind {
+        EventKind::Create(_) => Some("created"),
+        EventKind::Modify(_) => Some("modified"),
+        EventKind::Remove(_) => Some("removed"),
+        _ => None,
+    }
+}
+
+// Collects changes until the watcher has been quiet for DEBOUNCE, then
+// emits them. Ends once the watcher, and with it the sender, is dropped.
+fn debounce_radials(app: tauri::AppHandle, rx: mpsc::Receiver<(String, &'static str)>) {
+    let mut pending: BTreeMap<String, &'static str> = BTreeMap::new();
+    loop {
+        match rx.recv_timeout(DEBOUNCE) {
+            Ok((rel, kind)) => {
+                let merged = match (pending.get(&rel), kind) {
+                    // Still new as far as the frontend is concerned.
+                    (Some(&"created"), "modified") => "created",
+                    _ => kind,
+                };
+                pending.insert(rel, merged);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                for (path, kind) in std::mem::take(&mut pending) {
+                    if let Err(e) = app.emit("radials-changed", RadialsChange { path, kind }) {
+                        tracing::warn!("emit radials-changed failed: {e}");
+                    }
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+/// Watches `path`, subfolders included, and emits `radials-changed` as
+/// menus in it are created, modified or removed. Replaces any earlier
+/// radials watcher, so switching folders never leaves one behind.
+#[tauri::command]
+pub fn watch_radials_directory(path: String, app: tauri::AppHandle) -> Result<(), String> {
+    let dir = PathBuf::from(&path);
+    if !dir.is_dir() {
+        return Err(io_err(format!("directory {path} does not exist")));
+    }
+    // Events may arrive with the resolved path (e.g. /private/var on macOS).
+    let mut roots = vec![dir.clone()];
+    if let Ok(real) = fs::canonicalize(&dir) {
+        roots.push(real);
+    }
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        let Ok(event) = res else { return };
+        let Some(kind) = change_kind(&event.kind) else {
+            return;
+        };
+        for p in &event.paths {
+            if let Some(rel) = radials_rel(&roots, p) {
+                let _ = tx.send((rel, kind));
+            }
+        }
+    })
+    .map_err(|e| io_err(format!("create radials watcher failed: {e}")))?;
+    watcher
+        .watch(&dir, RecursiveMode::Recursive)
+        .map_err(|e| io_err(format!("watch {} failed: {e}", fmt_path(&dir))))?;
+    let handle = app.clone();
+    thread::spawn(move || debounce_radials(handle, rx));
+
+    let state = app.state::<Watchers>();
+    let mut slot = state
+        .radials
+        .lock()
+        .map_err(|_| io_err("watcher state poisoned"))?;
+    *slot = Some(watcher);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_watching_radials_directory(app: tauri::AppHandle) -> Result<(), String> {
+    let state = app.state::<Watchers>();
+    let mut slot = state
+        .radials
+        .lock()
+        .map_err(|_| io_err("watcher state poisoned"))?;
+    *slot = None;
+    Ok(())
+}
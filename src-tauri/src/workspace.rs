@@ -0,0 +1,64 @@
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    io_err,
+    prefs::{self, Preferences},
+    read_json_file, save_radials_directory, write_json_file,
+};
+
+pub const WORKSPACE_EXT: &str = "radialworkspace";
+
+/// The fields of a `.radialworkspace` file we understand. Parsing them
+/// type-checks the file; `directory` and `preferences` are applied on load.
+/// Anything else is the frontend's to shape and is kept as it is.
+#[derive(Deserialize)]
+struct Workspace {
+    directory: Option<String>,
+    // Only type-checked; reopening the menus is up to the frontend.
+    #[serde(default, rename = "recent_menus")]
+    _recent_menus: Vec<String>,
+    preferences: Option<Preferences>,
+}
+
+fn parse(value: &Value) -> Result<Workspace, String> {
+    serde_json::from_value(value.clone()).map_err(|e| io_err(format!("invalid workspace: {e}")))
+}
+
+fn check_ext(path: &Path) -> Result<(), String> {
+    if path.extension().and_then(|e| e.to_str()) != Some(WORKSPACE_EXT) {
+        return Err(io_err(format!(
+            "workspace files must end in .{WORKSPACE_EXT}"
+        )));
+    }
+    Ok(())
+}
+
+/// Saves the working context: `directory`, `recent_menus` and a
+/// `preferences` snapshot, plus whatever else the frontend keeps there.
+#[tauri::command]
+pub fn save_workspace(workspace: Value, path: String) -> Result<(), String> {
+    let path = Path::new(&path);
+    check_ext(path)?;
+    parse(&workspace)?;
+    write_json_file(path, &workspace)
+}
+
+/// Loads a workspace, points the saved radials directory at its
+/// `directory` and makes its preferences snapshot the active preferences.
+#[tauri::command]
+pub fn load_workspace(path: String, app: tauri::AppHandle) -> Result<Value, String> {
+    let path = Path::new(&path);
+    check_ext(path)?;
+    let value = read_json_file(path)?;
+    let workspace = parse(&value)?;
+    if let Some(preferences) = workspace.preferences {
+        prefs::set_preferences(preferences, app.clone())?;
+    }
+    if let Some(directory) = workspace.directory {
+        save_radials_directory(directory, app)?;
+    }
+    Ok(value)
+}